@@ -0,0 +1,16 @@
+// Run with `cargo bench --bench segmentation`; the time per input should grow linearly with its length
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rsnltk::native::segmentation::get_atomic_units;
+
+fn bench_atomic_units(c: &mut Criterion) {
+    let piece = "我用Rust 1.56写NLP，访问www.example.com或写信给dev@example.org。a.b.c ";
+    for n in [500, 4000] {
+        let text = piece.repeat(n);
+        c.bench_function(&format!("atomic units of {} pieces", n), |b| b.iter(|| get_atomic_units(black_box(&text))));
+    }
+    let run = "a".repeat(100000);
+    c.bench_function("atomic units of a 100000 letter run", |b| b.iter(|| get_atomic_units(black_box(&run))));
+}
+
+criterion_group!(benches, bench_atomic_units);
+criterion_main!(benches);
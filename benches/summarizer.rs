@@ -0,0 +1,30 @@
+// Run with `cargo bench --bench summarizer`, and `--features parallel` to compare the rayon build
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rsnltk::native::summarizer::{Summarizer, SummaryLength};
+
+///
+/// A long document of `n` sentences drawn from a small vocabulary
+///
+fn document(n: usize) -> String {
+    let vocabulary = ["market", "price", "rust", "memory", "summer", "river", "court", "vote", "energy", "school", "garden", "engine", "coffee", "music", "science", "travel"];
+    (0..n)
+        .map(|i| {
+            let words: Vec<&str> = (0..12).map(|k| vocabulary[(i * 7 + k * k * 3 + i / 5) % vocabulary.len()]).collect();
+            format!("Sentence {} says {}. ", i, words.join(" "))
+        })
+        .collect()
+}
+
+fn bench_summarizer(c: &mut Criterion) {
+    let summarizer = Summarizer::new();
+    for n in [100, 600] {
+        let text = document(n);
+        c.bench_function(&format!("rank {} sentences", n), |b| b.iter(|| summarizer.rank(black_box(&text))));
+        c.bench_function(&format!("summarize {} sentences", n), |b| {
+            b.iter(|| summarizer.summarize_ranked(black_box(&text), SummaryLength::Sentences(10)))
+        });
+    }
+}
+
+criterion_group!(benches, bench_summarizer);
+criterion_main!(benches);
@@ -1,4 +1,5 @@
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests{
     use crate::api::natural::*;
     # [test]
@@ -15,6 +16,7 @@ mod tests{
     # [test]
     fn test_tokenize(){
         let str1="hello, world!";
+        #[allow(unused_variables)]
         let srtr="My dog has fleas.";
         println!("{:?}",tokenize(str1));
 
@@ -37,6 +39,7 @@ mod tests{
     }
 
     # [test]
+    #[allow(clippy::vec_init_then_push)]
     fn test_classification(){
         let mut list_str=Vec::new();
         list_str.push("Hello World");
@@ -50,6 +53,7 @@ mod tests{
     }
 
     # [test]
+    #[allow(clippy::vec_init_then_push)]
     fn test_tf_idf(){
         let mut list_str=Vec::new();
         list_str.push("this document is about rust.");
@@ -71,20 +75,19 @@ use natural::classifier::NaiveBayesClassifier;
 use natural::tf_idf::TfIdf;
 
 pub fn lev_dist(str1:&str,str2:&str)->usize{
-    return levenshtein_distance(str1, str2);
+    levenshtein_distance(str1, str2)
 }
 
 pub fn jw_dist(str1:&str,str2:&str)->f32{
-    return jaro_winkler_distance(str1, str2);
+    jaro_winkler_distance(str1, str2)
 }
 
 pub fn is_soundex(str1:&str,str2:&str)->bool{
-    let result=soundex(str1,str2);
-    return result;
+    soundex(str1,str2)
 }
 
 pub fn tokenize(str:&str)->Vec<&str>{
-    natural::tokenize::tokenize(&str)
+    natural::tokenize::tokenize(str)
 }
 
 
@@ -101,16 +104,17 @@ pub fn nb_guess(train_strs:Vec<&str>,labels:Vec<&str>,str_guess:&str)->String{
 
 
     let mut nbc = NaiveBayesClassifier::new();
-    let mut idx=0;
-    for train_str in train_strs{
+    for (idx,train_str) in train_strs.into_iter().enumerate(){
         nbc.train(train_str, labels[idx]);
-        idx+=1;
     }
 
     nbc.guess(str_guess) //returns a label with the highest probability
 
 }
 
+///
+/// TF-IDF of a term against a small corpus. See `native::keywords` to extract the keywords of a document.
+///
 pub fn get_tf_idf(strs:Vec<&str>,s:&str)->f32{
 
     let mut tf_idf=TfIdf::new();
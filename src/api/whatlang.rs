@@ -1,4 +1,5 @@
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use crate::api::whatlang::*;
     # [test]
@@ -8,6 +9,12 @@ mod tests {
         println!("{:?}",ret);
     }
 
+    # [test]
+    fn test_try_whatlang_undetectable(){
+        assert!(try_whatlang("").is_err());
+        assert!(whatlang("").is_empty());
+    }
+
 }
 
 use std::collections::HashMap;
@@ -15,17 +22,34 @@ use std::collections::HashMap;
 
 extern crate whatlang;
 use whatlang::detect;
-pub fn whatlang(str:&str) ->HashMap<String,String>{
+use log::warn;
+use crate::error::{Error, Result};
 
+///
+/// Detect the language, script and confidence of a text. Returns an empty map when detection fails.
+///
+pub fn whatlang(str:&str) ->HashMap<String,String>{
+    match try_whatlang(str){
+        Ok(result)=>result,
+        Err(e)=>{
+            warn!("{}",e);
+            HashMap::new()
+        }
+    }
+}
 
-    let info = detect(str).unwrap();
+///
+/// Same as `whatlang`, but returns `Error::UnsupportedLanguage` when no language can be detected
+///
+pub fn try_whatlang(str:&str) ->Result<HashMap<String,String>>{
+    let info = detect(str).ok_or_else(|| Error::UnsupportedLanguage(format!("unable to detect the language of {:?}",str)))?;
     let mut result:HashMap<String,String>=HashMap::new();
     result.insert(String::from("lang"),info.lang().to_string());
     result.insert(String::from("script"),info.script().to_string());
     result.insert(String::from("confidence"),info.confidence().to_string());
     result.insert(String::from("is_reliable"),info.is_reliable().to_string());
 
-    result
+    Ok(result)
 
 }
 
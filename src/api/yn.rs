@@ -1,4 +1,5 @@
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use crate::api::yn::*;
     # [test]
@@ -15,10 +16,10 @@ mod tests {
 extern crate yn;
 
 pub fn yes(str:&str)->bool{
-    return yn::yes(str);
+    yn::yes(str)
 }
 pub fn is_somewhat_yes(str:&str)->bool{
-    return yn::is_somewhat_yes(str);
+    yn::is_somewhat_yes(str)
 }
 
 pub fn is_kinda_yes(str:&str)->bool{
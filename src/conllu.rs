@@ -0,0 +1,349 @@
+use std::fs;
+use std::path::Path;
+use crate::document::{format_feats, parse_feats, EmptyNode, Sentence, Token, Word};
+use crate::error::{Error, Result};
+
+#[cfg(test)]
+#[allow(clippy::items_after_test_module)]
+mod tests {
+    use crate::conllu::*;
+
+    const SAMPLE: &str = "# sent_id = 1
+# text = Nous avons atteint la fin du sentier.
+1\tNous\til\tPRON\t_\tNumber=Plur|Person=1|PronType=Prs\t3\tnsubj\t3:nsubj\tstart_char=0|end_char=4
+2\tavons\tavoir\tAUX\t_\tMood=Ind|Number=Plur|Person=1|Tense=Pres|VerbForm=Fin\t3\taux:tense\t3:aux:tense\tstart_char=5|end_char=10
+3\tatteint\tatteindre\tVERB\t_\tGender=Masc|Number=Sing|Tense=Past|VerbForm=Part\t0\troot\t0:root\tstart_char=11|end_char=18
+4\tla\tle\tDET\t_\tDefinite=Def|Gender=Fem|Number=Sing|PronType=Art\t5\tdet\t5:det\tstart_char=19|end_char=21
+5\tfin\tfin\tNOUN\t_\tGender=Fem|Number=Sing\t3\tobj\t3:obj\tstart_char=22|end_char=25
+6-7\tdu\t_\t_\t_\t_\t_\t_\t_\tstart_char=26|end_char=28
+6\tde\tde\tADP\t_\t_\t8\tcase\t8:case\t_
+7\tle\tle\tDET\t_\tDefinite=Def|Gender=Masc|Number=Sing|PronType=Art\t8\tdet\t8:det\t_
+8\tsentier\tsentier\tNOUN\t_\tGender=Masc|Number=Sing\t5\tnmod\t5:nmod:de\tstart_char=29|end_char=36|SpaceAfter=No
+9\t.\t.\tPUNCT\t_\t_\t3\tpunct\t3:punct\tstart_char=36|end_char=37
+
+# sent_id = 2
+# text = Sue likes coffee!
+1\tSue\tSue\tPROPN\t_\t_\t2\tnsubj\t2:nsubj|2.1:nsubj\t_
+2\tlikes\tlike\tVERB\t_\t_\t0\troot\t0:root\t_
+2.1\tlikes\tlike\tVERB\t_\t_\t_\t_\t2:conj\tCopyOf=2
+3\tcoffee\tcoffee\tNOUN\t_\t_\t2\tobj\t2:obj\tSpaceAfter=No
+4\t!\t!\tPUNCT\t_\t_\t2\tpunct\t2:punct\t_
+
+";
+
+    #[test]
+    fn test_round_trip() {
+        let sentences = parse(SAMPLE).unwrap();
+        assert_eq!(sentences.len(), 2);
+        assert_eq!(to_string(&sentences), SAMPLE);
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        let sentences = parse(SAMPLE).unwrap();
+        let first = &sentences[0];
+        assert_eq!(first.text, "Nous avons atteint la fin du sentier.");
+        assert_eq!(first.tokens.len(), 8);
+        assert_eq!(first.tokens[5].id, (6, 7));
+        assert_eq!(first.tokens[5].start_char, Some(26));
+        assert_eq!(first.words().count(), 9);
+        let verb = first.word(3).unwrap();
+        assert!(verb.is_root());
+        assert_eq!(verb.feats["VerbForm"], "Part");
+        assert_eq!(first.word(8).unwrap().deps.as_deref(), Some("5:nmod:de"));
+
+        let second = &sentences[1];
+        assert_eq!(second.text, "Sue likes coffee!");
+        assert_eq!(second.empty_nodes.len(), 1);
+        assert_eq!(second.empty_nodes[0].id, (2, 1));
+    }
+
+    #[test]
+    fn test_text_from_tokens() {
+        let input = "1\tHello\t_\t_\t_\t_\t0\troot\t_\tSpaceAfter=No\n2\t!\t_\t_\t_\t_\t1\tpunct\t_\t_\n\n";
+        let sentences = parse(input).unwrap();
+        assert_eq!(sentences[0].text, "Hello!");
+        assert!(to_string(&sentences).starts_with("# text = Hello!\n1\tHello"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("1\tonly\tthree\n").is_err());
+        assert!(parse("x\ta\tb\tc\td\te\tf\tg\th\ti\n").is_err());
+        // a range with no words after it
+        let range = "1\tNous\til\tPRON\t_\t_\t0\troot\t_\t_\n2-2\tdu\t_\t_\t_\t_\t_\t_\t_\t_\n";
+        match parse(range) {
+            Err(Error::Parse(message)) => assert!(message.starts_with("CoNLL-U line 2:"), "{}", message),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        assert!(parse(&format!("{}\n1\ta\t_\t_\t_\t_\t0\troot\t_\t_\n", range)).is_err());
+    }
+}
+
+///
+/// Parse CoNLL-U text into sentences. Comments, multi-word token ranges ("6-7"),
+/// empty nodes ("2.1") and the enhanced DEPS column are kept so that `to_string` gives the input back.
+/// Character offsets are read from "start_char=..|end_char=.." in the MISC column, as written by Stanza.
+///
+pub fn parse(input: &str) -> Result<Vec<Sentence>> {
+    let mut sentences = Vec::new();
+    let mut current = Sentence::default();
+    let mut has_content = false;
+    let mut open_range: Option<(usize, usize)> = None;
+    let mut token_lines = Vec::new();
+    for (line_no, line) in input.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            if has_content {
+                sentences.push(finish_sentence(current, &token_lines)?);
+                current = Sentence::default();
+                has_content = false;
+                open_range = None;
+                token_lines.clear();
+            }
+            continue;
+        }
+        has_content = true;
+        if let Some(comment) = line.strip_prefix('#') {
+            current.comments.push(comment.strip_prefix(' ').unwrap_or(comment).to_string());
+            continue;
+        }
+        let cols: Vec<&str> = line.split('\t').collect();
+        if cols.len() != 10 {
+            return Err(parse_error(line_no, &format!("expected 10 columns, found {}", cols.len())));
+        }
+        let id = cols[0];
+        if let Some((start, end)) = id.split_once('-') {
+            let range = (parse_index(start, line_no)?, parse_index(end, line_no)?);
+            let (start_char, end_char) = offsets(cols[9]);
+            current.tokens.push(Token {
+                id: range,
+                text: cols[1].to_string(),
+                start_char,
+                end_char,
+                misc: column(cols[9]),
+                ..Default::default()
+            });
+            token_lines.push(line_no);
+            open_range = Some(range);
+        } else if let Some((major, minor)) = id.split_once('.') {
+            current.empty_nodes.push(EmptyNode {
+                id: (parse_index(major, line_no)?, parse_index(minor, line_no)?),
+                text: cols[1].to_string(),
+                lemma: column(cols[2]),
+                upos: column(cols[3]),
+                xpos: column(cols[4]),
+                feats: parse_feats(cols[5]),
+                deps: column(cols[8]),
+                misc: column(cols[9]),
+            });
+        } else {
+            let word = parse_word(&cols, line_no)?;
+            match open_range {
+                Some((start, end)) if word.id >= start && word.id <= end => {
+                    if word.id == end {
+                        open_range = None;
+                    }
+                    current.tokens.last_mut().unwrap().words.push(word);
+                }
+                _ => {
+                    current.tokens.push(Token {
+                        id: (word.id, word.id),
+                        text: word.text.clone(),
+                        start_char: word.start_char,
+                        end_char: word.end_char,
+                        words: vec![word],
+                        ..Default::default()
+                    });
+                    token_lines.push(line_no);
+                    open_range = None;
+                }
+            }
+        }
+    }
+    if has_content {
+        sentences.push(finish_sentence(current, &token_lines)?);
+    }
+    Ok(sentences)
+}
+
+///
+/// Read a CoNLL-U file
+///
+pub fn read_file<P: AsRef<Path>>(path: P) -> Result<Vec<Sentence>> {
+    parse(&fs::read_to_string(path)?)
+}
+
+///
+/// Write sentences as CoNLL-U text. A "# text = ..." comment is added when the sentence has none,
+/// and Stanza character offsets are written to the MISC column when it is empty.
+///
+pub fn to_string(sentences: &[Sentence]) -> String {
+    let mut out = String::new();
+    for sentence in sentences {
+        for comment in &sentence.comments {
+            out.push_str("# ");
+            out.push_str(comment);
+            out.push('\n');
+        }
+        if !sentence.text.is_empty() && !sentence.comments.iter().any(|c| c.starts_with("text =")) {
+            out.push_str(&format!("# text = {}\n", sentence.text));
+        }
+        write_empty_nodes(&mut out, sentence, 0);
+        for token in &sentence.tokens {
+            if token.is_multiword() {
+                out.push_str(&format!(
+                    "{}-{}\t{}\t_\t_\t_\t_\t_\t_\t_\t{}\n",
+                    token.id.0,
+                    token.id.1,
+                    token.text,
+                    misc_or_offsets(&token.misc, token.start_char, token.end_char)
+                ));
+            }
+            for word in &token.words {
+                out.push_str(&format_word(word, token));
+                write_empty_nodes(&mut out, sentence, word.id);
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+///
+/// Write sentences to a CoNLL-U file
+///
+pub fn write_file<P: AsRef<Path>>(path: P, sentences: &[Sentence]) -> Result<()> {
+    fs::write(path, to_string(sentences))?;
+    Ok(())
+}
+
+fn parse_word(cols: &[&str], line_no: usize) -> Result<Word> {
+    let (start_char, end_char) = offsets(cols[9]);
+    let head = match cols[6] {
+        "_" => None,
+        h => Some(parse_index(h, line_no)?),
+    };
+    Ok(Word {
+        id: parse_index(cols[0], line_no)?,
+        text: cols[1].to_string(),
+        lemma: column(cols[2]),
+        upos: column(cols[3]),
+        xpos: column(cols[4]),
+        feats: parse_feats(cols[5]),
+        head,
+        deprel: column(cols[7]),
+        deps: column(cols[8]),
+        misc: column(cols[9]),
+        start_char,
+        end_char,
+    })
+}
+
+fn format_word(word: &Word, token: &Token) -> String {
+    let misc = if token.is_multiword() {
+        word.misc.clone().unwrap_or_else(|| "_".to_string())
+    } else {
+        misc_or_offsets(&word.misc, word.start_char, word.end_char)
+    };
+    format!(
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+        word.id,
+        word.text,
+        or_blank(&word.lemma),
+        or_blank(&word.upos),
+        or_blank(&word.xpos),
+        format_feats(&word.feats),
+        word.head.map(|h| h.to_string()).unwrap_or_else(|| "_".to_string()),
+        or_blank(&word.deprel),
+        or_blank(&word.deps),
+        misc
+    )
+}
+
+fn write_empty_nodes(out: &mut String, sentence: &Sentence, after: usize) {
+    for node in sentence.empty_nodes.iter().filter(|n| n.id.0 == after) {
+        out.push_str(&format!(
+            "{}.{}\t{}\t{}\t{}\t{}\t{}\t_\t_\t{}\t{}\n",
+            node.id.0,
+            node.id.1,
+            node.text,
+            or_blank(&node.lemma),
+            or_blank(&node.upos),
+            or_blank(&node.xpos),
+            format_feats(&node.feats),
+            or_blank(&node.deps),
+            or_blank(&node.misc)
+        ));
+    }
+}
+
+///
+/// Fill in the sentence text from the "# text = ..." comment, or rebuild it from the tokens honouring SpaceAfter=No.
+/// `token_lines` holds the input line of each token, for reporting a range line with no words after it.
+///
+fn finish_sentence(mut sentence: Sentence, token_lines: &[usize]) -> Result<Sentence> {
+    let mut first_words = Vec::with_capacity(sentence.tokens.len());
+    for (token, &line_no) in sentence.tokens.iter().zip(token_lines) {
+        match token.words.first() {
+            Some(word) => first_words.push(word),
+            None => return Err(parse_error(line_no, &format!("token {}-{} has no words", token.id.0, token.id.1))),
+        }
+    }
+    if let Some(text) = sentence.comments.iter().find_map(|c| c.strip_prefix("text = ")) {
+        sentence.text = text.to_string();
+        return Ok(sentence);
+    }
+    let mut text = String::new();
+    for (token, word) in sentence.tokens.iter().zip(first_words) {
+        text.push_str(&token.text);
+        let misc = if token.is_multiword() { &token.misc } else { &word.misc };
+        let no_space = misc.as_deref().map(|m| m.split('|').any(|p| p == "SpaceAfter=No")).unwrap_or(false);
+        if !no_space {
+            text.push(' ');
+        }
+    }
+    sentence.text = text.trim_end().to_string();
+    Ok(sentence)
+}
+
+fn misc_or_offsets(misc: &Option<String>, start_char: Option<usize>, end_char: Option<usize>) -> String {
+    match (misc, start_char, end_char) {
+        (Some(m), _, _) => m.clone(),
+        (None, Some(s), Some(e)) => format!("start_char={}|end_char={}", s, e),
+        _ => "_".to_string(),
+    }
+}
+
+fn offsets(misc: &str) -> (Option<usize>, Option<usize>) {
+    let mut start = None;
+    let mut end = None;
+    for part in misc.split('|') {
+        if let Some(v) = part.strip_prefix("start_char=") {
+            start = v.parse().ok();
+        } else if let Some(v) = part.strip_prefix("end_char=") {
+            end = v.parse().ok();
+        }
+    }
+    (start, end)
+}
+
+fn column(value: &str) -> Option<String> {
+    if value == "_" {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+fn or_blank(value: &Option<String>) -> &str {
+    value.as_deref().unwrap_or("_")
+}
+
+fn parse_index(value: &str, line_no: usize) -> Result<usize> {
+    value.parse().map_err(|_| parse_error(line_no, &format!("invalid id or head {:?}", value)))
+}
+
+fn parse_error(line_no: usize, message: &str) -> Error {
+    Error::Parse(format!("CoNLL-U line {}: {}", line_no + 1, message))
+}
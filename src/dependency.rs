@@ -0,0 +1,269 @@
+use crate::document::{Sentence, Word};
+use crate::error::{Error, Result};
+
+#[cfg(test)]
+mod tests {
+    use crate::conllu;
+    use crate::dependency::*;
+
+    // "The quick fox jumped over the dog ." with "jumped" as root
+    const SAMPLE: &str = "1\tThe\t_\tDET\t_\t_\t3\tdet\t_\t_
+2\tquick\t_\tADJ\t_\t_\t3\tamod\t_\t_
+3\tfox\t_\tNOUN\t_\t_\t4\tnsubj\t_\t_
+4\tjumped\t_\tVERB\t_\t_\t0\troot\t_\t_
+5\tover\t_\tADP\t_\t_\t7\tcase\t_\t_
+6\tthe\t_\tDET\t_\t_\t7\tdet\t_\t_
+7\tdog\t_\tNOUN\t_\t_\t4\tobl\t_\t_
+8\t.\t_\tPUNCT\t_\t_\t4\tpunct\t_\t_
+
+";
+
+    fn tree(input: &str) -> DependencyTree {
+        let sentences = conllu::parse(input).unwrap();
+        DependencyTree::from_sentence(&sentences[0]).unwrap()
+    }
+
+    #[test]
+    fn test_navigation() {
+        let t = tree(SAMPLE);
+        assert_eq!(t.root().unwrap().text, "jumped");
+        let children: Vec<usize> = t.children(4).iter().map(|w| w.id).collect();
+        assert_eq!(children, vec![3, 7, 8]);
+        let ancestors: Vec<usize> = t.ancestors(1).iter().map(|w| w.id).collect();
+        assert_eq!(ancestors, vec![3, 4]);
+        assert_eq!(t.subtree(7), vec![5, 6, 7]);
+        assert_eq!(t.subtree_span(3), (1, 3));
+        assert_eq!(t.depth(6), 2);
+    }
+
+    #[test]
+    fn test_path_and_lca() {
+        let t = tree(SAMPLE);
+        assert_eq!(t.lowest_common_ancestor(1, 6), Some(4));
+        assert_eq!(t.lowest_common_ancestor(5, 7), Some(7));
+        assert_eq!(t.path(2, 5), vec![2, 3, 4, 7, 5]);
+        assert_eq!(t.path(7, 7), vec![7]);
+    }
+
+    #[test]
+    fn test_projectivity() {
+        assert!(tree(SAMPLE).is_projective());
+        let non_projective = "1\tA\t_\t_\t_\t_\t3\tdep\t_\t_
+2\thearing\t_\t_\t_\t_\t4\tdep\t_\t_
+3\tis\t_\t_\t_\t_\t0\troot\t_\t_
+4\tscheduled\t_\t_\t_\t_\t1\tdep\t_\t_
+
+";
+        assert!(!tree(non_projective).is_projective());
+    }
+
+    #[test]
+    fn test_invalid_trees() {
+        let cycle = "1\ta\t_\t_\t_\t_\t2\tdep\t_\t_\n2\tb\t_\t_\t_\t_\t1\tdep\t_\t_\n\n";
+        let sentences = conllu::parse(cycle).unwrap();
+        assert!(DependencyTree::from_sentence(&sentences[0]).is_err());
+        let missing_head = "1\ta\t_\t_\t_\t_\t_\t_\t_\t_\n\n";
+        let sentences = conllu::parse(missing_head).unwrap();
+        assert!(DependencyTree::from_sentence(&sentences[0]).is_err());
+    }
+}
+
+///
+/// A dependency tree over the words of a parsed sentence. Words are addressed by their 1-based id,
+/// and 0 stands for the virtual root above the sentence.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct DependencyTree {
+    words: Vec<Word>,
+    heads: Vec<usize>,
+    children: Vec<Vec<usize>>,
+}
+
+impl DependencyTree {
+    ///
+    /// Build the tree of a sentence parsed by `dependency_tree` or read from CoNLL-U.
+    /// Fails when a head is missing, points outside the sentence or the heads form a cycle.
+    ///
+    pub fn from_sentence(sentence: &Sentence) -> Result<DependencyTree> {
+        let words: Vec<Word> = sentence.words().cloned().collect();
+        let n = words.len();
+        let mut heads = vec![0; n + 1];
+        let mut children = vec![Vec::new(); n + 1];
+        for (i, word) in words.iter().enumerate() {
+            if word.id != i + 1 {
+                return Err(Error::Parse(format!("word ids are not consecutive at {:?}", word.text)));
+            }
+            let head = word.head.ok_or_else(|| Error::Parse(format!("word {} {:?} has no head", word.id, word.text)))?;
+            if head > n {
+                return Err(Error::Parse(format!("word {} points to missing head {}", word.id, head)));
+            }
+            heads[word.id] = head;
+            children[head].push(word.id);
+        }
+        let tree = DependencyTree { words, heads, children };
+        for id in 1..=n {
+            if tree.ancestor_ids(id).len() > n {
+                return Err(Error::Parse(format!("word {} is part of a cycle", id)));
+            }
+        }
+        Ok(tree)
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    pub fn word(&self, id: usize) -> Option<&Word> {
+        if id == 0 {
+            None
+        } else {
+            self.words.get(id - 1)
+        }
+    }
+
+    ///
+    /// The word attached to the virtual root
+    ///
+    pub fn root(&self) -> Option<&Word> {
+        self.children[0].first().and_then(|&id| self.word(id))
+    }
+
+    ///
+    /// The head of a word, or None for the root
+    ///
+    pub fn head(&self, id: usize) -> Option<&Word> {
+        self.heads.get(id).and_then(|&h| self.word(h))
+    }
+
+    ///
+    /// Direct dependents of a word in sentence order
+    ///
+    pub fn children(&self, id: usize) -> Vec<&Word> {
+        self.children.get(id).map(|c| c.iter().filter_map(|&i| self.word(i)).collect()).unwrap_or_default()
+    }
+
+    ///
+    /// Heads from the word up to the root, nearest first
+    ///
+    pub fn ancestors(&self, id: usize) -> Vec<&Word> {
+        self.ancestor_ids(id).into_iter().filter_map(|i| self.word(i)).collect()
+    }
+
+    ///
+    /// Number of arcs between the word and the root; the root has depth 0
+    ///
+    pub fn depth(&self, id: usize) -> usize {
+        self.ancestor_ids(id).len()
+    }
+
+    ///
+    /// Ids of the word and all its descendants, sorted
+    ///
+    pub fn subtree(&self, id: usize) -> Vec<usize> {
+        let mut ids = Vec::new();
+        let mut stack = vec![id];
+        while let Some(current) = stack.pop() {
+            if current != 0 {
+                ids.push(current);
+            }
+            if let Some(c) = self.children.get(current) {
+                stack.extend(c.iter().copied());
+            }
+        }
+        ids.sort_unstable();
+        ids
+    }
+
+    ///
+    /// First and last word ids covered by the subtree of a word
+    ///
+    pub fn subtree_span(&self, id: usize) -> (usize, usize) {
+        let ids = self.subtree(id);
+        (ids.first().copied().unwrap_or(id), ids.last().copied().unwrap_or(id))
+    }
+
+    ///
+    /// The closest word dominating both words (a word dominates itself), or 0 when they only meet at the virtual root
+    ///
+    pub fn lowest_common_ancestor(&self, a: usize, b: usize) -> Option<usize> {
+        if self.word(a).is_none() || self.word(b).is_none() {
+            return None;
+        }
+        let mut chain_a = vec![a];
+        chain_a.extend(self.ancestor_ids(a));
+        let mut chain_b = vec![b];
+        chain_b.extend(self.ancestor_ids(b));
+        Some(chain_a.into_iter().find(|x| chain_b.contains(x)).unwrap_or(0))
+    }
+
+    ///
+    /// Word ids on the tree path from `a` to `b`, both included, going up to their lowest common ancestor and down again
+    ///
+    pub fn path(&self, a: usize, b: usize) -> Vec<usize> {
+        let lca = match self.lowest_common_ancestor(a, b) {
+            Some(lca) => lca,
+            None => return Vec::new(),
+        };
+        let mut path = self.chain_to(a, lca);
+        if lca != 0 {
+            path.push(lca);
+        }
+        let mut down = self.chain_to(b, lca);
+        down.reverse();
+        path.extend(down);
+        path
+    }
+
+    ///
+    /// Whether no two arcs cross when drawn above the sentence, counting the arc from the virtual root at position 0
+    ///
+    pub fn is_projective(&self) -> bool {
+        let arcs: Vec<(usize, usize)> = (1..self.heads.len())
+            .map(|d| {
+                let h = self.heads[d];
+                (h.min(d), h.max(d))
+            })
+            .collect();
+        for (i, &(a1, b1)) in arcs.iter().enumerate() {
+            for &(a2, b2) in &arcs[i + 1..] {
+                if (a1 < a2 && a2 < b1 && b1 < b2) || (a2 < a1 && a1 < b2 && b2 < b1) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    pub fn words(&self) -> &[Word] {
+        &self.words
+    }
+
+    fn chain_to(&self, id: usize, ancestor: usize) -> Vec<usize> {
+        let mut ids = Vec::new();
+        let mut current = id;
+        while current != ancestor && current != 0 {
+            ids.push(current);
+            current = self.heads[current];
+        }
+        ids
+    }
+
+    fn ancestor_ids(&self, id: usize) -> Vec<usize> {
+        let mut ids = Vec::new();
+        let mut current = id;
+        while current != 0 && ids.len() <= self.words.len() {
+            current = match self.heads.get(current) {
+                Some(&h) => h,
+                None => break,
+            };
+            if current != 0 {
+                ids.push(current);
+            }
+        }
+        ids
+    }
+}
@@ -0,0 +1,367 @@
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::document::*;
+
+    fn map(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_parse_feats() {
+        let feats = parse_feats("Number=Sing|Person=1|PronType=Prs");
+        assert_eq!(feats.len(), 3);
+        assert_eq!(feats["Person"], "1");
+        assert_eq!(format_feats(&feats), "Number=Sing|Person=1|PronType=Prs");
+        assert!(parse_feats("_").is_empty());
+    }
+
+    #[test]
+    fn test_word_from_map() {
+        let word = Word::from_map(&map(&[
+            ("id", "2"), ("text", "like"), ("lemma", "like"), ("upos", "VERB"),
+            ("feats", "Mood=Ind|Tense=Pres"), ("head", "0"), ("deprel", "root"),
+            ("start_char", "2"), ("end_char", "6"),
+        ]));
+        assert_eq!(word.id, 2);
+        assert_eq!(word.head, Some(0));
+        assert_eq!(word.upos.as_deref(), Some("VERB"));
+        assert_eq!(word.feats["Tense"], "Pres");
+        assert_eq!((word.start_char, word.end_char), (Some(2), Some(6)));
+    }
+
+    #[test]
+    fn test_sentence_with_multiword_token() {
+        let rows = vec![
+            map(&[("id", "(1, 2)"), ("text", "du"), ("start_char", "0"), ("end_char", "2")]),
+            map(&[("id", "1"), ("text", "de"), ("upos", "ADP")]),
+            map(&[("id", "2"), ("text", "le"), ("upos", "DET")]),
+            map(&[("id", "3"), ("text", "sentier"), ("start_char", "3"), ("end_char", "10")]),
+        ];
+        let sentence = Sentence::from_maps(&rows);
+        assert_eq!(sentence.tokens.len(), 2);
+        assert_eq!(sentence.tokens[0].id, (1, 2));
+        assert!(sentence.tokens[0].is_multiword());
+        assert_eq!(sentence.words().count(), 3);
+        assert_eq!(sentence.text, "du sentier");
+    }
+}
+
+///
+/// A document annotated by Stanza or read from a treebank, made of sentences.
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Document {
+    pub text: String,
+    pub sentences: Vec<Sentence>,
+}
+
+impl Document {
+    ///
+    /// Iterate over all words of all sentences
+    ///
+    pub fn words(&self) -> impl Iterator<Item = &Word> {
+        self.sentences.iter().flat_map(|s| s.words())
+    }
+
+    ///
+    /// Iterate over all named entities of all sentences
+    ///
+    pub fn entities(&self) -> impl Iterator<Item = &Entity> {
+        self.sentences.iter().flat_map(|s| s.entities.iter())
+    }
+}
+
+///
+/// A sentence made of tokens. Each token holds one or more syntactic words.
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sentence {
+    pub text: String,
+    pub tokens: Vec<Token>,
+    pub entities: Vec<Entity>,
+    pub sentiment: Option<Sentiment>,
+    /// Comment lines of a CoNLL-U sentence without the leading "# ", e.g. "sent_id = 1"
+    pub comments: Vec<String>,
+    /// Empty nodes (ids like "8.1") used by enhanced dependencies
+    pub empty_nodes: Vec<EmptyNode>,
+}
+
+impl Sentence {
+    ///
+    /// Iterate over the syntactic words of the sentence, with multi-word tokens expanded
+    ///
+    pub fn words(&self) -> impl Iterator<Item = &Word> {
+        self.tokens.iter().flat_map(|t| t.words.iter())
+    }
+
+    ///
+    /// Find a word by its 1-based index in the sentence
+    ///
+    pub fn word(&self, id: usize) -> Option<&Word> {
+        self.words().find(|w| w.id == id)
+    }
+
+    ///
+    /// Build a sentence from the flat list of maps returned by Stanza's `Sentence.to_dict()`,
+    /// where a multi-word token (id like "(1, 2)" or "1-2") is followed by its words.
+    ///
+    pub fn from_maps(rows: &[HashMap<String, String>]) -> Sentence {
+        let mut tokens: Vec<Token> = Vec::new();
+        let mut open_range: Option<(usize, usize)> = None;
+        for row in rows {
+            let id = row.get("id").map(|s| parse_id(s)).unwrap_or((0, 0));
+            if id.0 != id.1 {
+                tokens.push(Token::from_map(row));
+                open_range = Some(id);
+                continue;
+            }
+            let word = Word::from_map(row);
+            match open_range {
+                Some((start, end)) if word.id >= start && word.id <= end => {
+                    tokens.last_mut().unwrap().words.push(word);
+                    if word_is_last(end, tokens.last().unwrap()) {
+                        open_range = None;
+                    }
+                }
+                _ => {
+                    let mut token = Token::from_map(row);
+                    token.words.push(word);
+                    tokens.push(token);
+                    open_range = None;
+                }
+            }
+        }
+        let text = tokens_text(&tokens);
+        Sentence { text, tokens, ..Default::default() }
+    }
+}
+
+fn word_is_last(end: usize, token: &Token) -> bool {
+    token.words.last().map(|w| w.id == end).unwrap_or(false)
+}
+
+///
+/// Rebuild the surface text of a list of tokens, using character offsets to restore spacing when known
+///
+fn tokens_text(tokens: &[Token]) -> String {
+    let mut text = String::new();
+    let mut last_end: Option<usize> = None;
+    for token in tokens {
+        if let Some(prev) = last_end {
+            match token.start_char {
+                Some(start) if start == prev => {}
+                _ => text.push(' '),
+            }
+        }
+        text.push_str(&token.text);
+        last_end = token.end_char;
+    }
+    text
+}
+
+///
+/// A token as it appears in the raw text. `id` is the range of word indices it covers,
+/// e.g. (3, 3) for a plain token or (3, 4) for a multi-word token such as French "du".
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Token {
+    pub id: (usize, usize),
+    pub text: String,
+    pub start_char: Option<usize>,
+    pub end_char: Option<usize>,
+    pub ner: Option<String>,
+    pub misc: Option<String>,
+    pub words: Vec<Word>,
+}
+
+impl Token {
+    pub fn is_multiword(&self) -> bool {
+        self.id.0 != self.id.1
+    }
+
+    ///
+    /// Build a token (without words) from a Stanza token map
+    ///
+    pub fn from_map(row: &HashMap<String, String>) -> Token {
+        Token {
+            id: row.get("id").map(|s| parse_id(s)).unwrap_or((0, 0)),
+            text: row.get("text").cloned().unwrap_or_default(),
+            start_char: get_usize(row, "start_char"),
+            end_char: get_usize(row, "end_char"),
+            ner: get_string(row, "ner"),
+            misc: get_string(row, "misc"),
+            words: Vec::new(),
+        }
+    }
+}
+
+///
+/// A syntactic word with its part-of-speech, morphological features and dependency relation.
+/// `id` is 1-based within the sentence and `head` is 0 for the root.
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Word {
+    pub id: usize,
+    pub text: String,
+    pub lemma: Option<String>,
+    pub upos: Option<String>,
+    pub xpos: Option<String>,
+    pub feats: BTreeMap<String, String>,
+    pub head: Option<usize>,
+    pub deprel: Option<String>,
+    pub deps: Option<String>,
+    pub misc: Option<String>,
+    pub start_char: Option<usize>,
+    pub end_char: Option<usize>,
+}
+
+impl Word {
+    ///
+    /// Build a word from a Stanza word map with keys such as "id", "text", "upos" and "head"
+    ///
+    pub fn from_map(row: &HashMap<String, String>) -> Word {
+        Word {
+            id: row.get("id").map(|s| parse_id(s).0).unwrap_or(0),
+            text: row.get("text").cloned().unwrap_or_default(),
+            lemma: get_string(row, "lemma"),
+            upos: get_string(row, "upos"),
+            xpos: get_string(row, "xpos"),
+            feats: row.get("feats").map(|s| parse_feats(s)).unwrap_or_default(),
+            head: get_usize(row, "head"),
+            deprel: get_string(row, "deprel"),
+            deps: get_string(row, "deps"),
+            misc: get_string(row, "misc"),
+            start_char: get_usize(row, "start_char"),
+            end_char: get_usize(row, "end_char"),
+        }
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.head == Some(0)
+    }
+}
+
+///
+/// An empty node of an enhanced dependency graph, e.g. an elided predicate. `id` is (8, 1) for "8.1".
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EmptyNode {
+    pub id: (usize, usize),
+    pub text: String,
+    pub lemma: Option<String>,
+    pub upos: Option<String>,
+    pub xpos: Option<String>,
+    pub feats: BTreeMap<String, String>,
+    pub deps: Option<String>,
+    pub misc: Option<String>,
+}
+
+///
+/// A named entity mention, e.g. "Beijing" of type "GPE"
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Entity {
+    pub text: String,
+    pub entity_type: String,
+    pub start_char: usize,
+    pub end_char: usize,
+}
+
+impl Entity {
+    ///
+    /// Build an entity from a Stanza entity map with keys "text", "type", "start_char" and "end_char"
+    ///
+    pub fn from_map(row: &HashMap<String, String>) -> Entity {
+        Entity {
+            text: row.get("text").cloned().unwrap_or_default(),
+            entity_type: row.get("type").cloned().unwrap_or_default(),
+            start_char: get_usize(row, "start_char").unwrap_or(0),
+            end_char: get_usize(row, "end_char").unwrap_or(0),
+        }
+    }
+}
+
+///
+/// Sentence-level sentiment, encoded by Stanza as 0, 1 and 2
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sentiment {
+    Negative,
+    Neutral,
+    Positive,
+}
+
+impl Sentiment {
+    pub fn from_index(index: usize) -> Option<Sentiment> {
+        match index {
+            0 => Some(Sentiment::Negative),
+            1 => Some(Sentiment::Neutral),
+            2 => Some(Sentiment::Positive),
+            _ => None,
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        match self {
+            Sentiment::Negative => 0,
+            Sentiment::Neutral => 1,
+            Sentiment::Positive => 2,
+        }
+    }
+}
+
+///
+/// Parse a UD feature string like "Number=Sing|Person=1" into a sorted map. "_" or "" gives an empty map.
+///
+pub fn parse_feats(s: &str) -> BTreeMap<String, String> {
+    let mut feats = BTreeMap::new();
+    if s.is_empty() || s == "_" {
+        return feats;
+    }
+    for pair in s.split('|') {
+        if let Some((k, v)) = pair.split_once('=') {
+            feats.insert(k.to_string(), v.to_string());
+        }
+    }
+    feats
+}
+
+///
+/// Format a feature map back to the UD "Key=Value|Key=Value" form, or "_" when empty
+///
+pub fn format_feats(feats: &BTreeMap<String, String>) -> String {
+    if feats.is_empty() {
+        return "_".to_string();
+    }
+    feats.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<String>>().join("|")
+}
+
+///
+/// Parse a Stanza id which is either a single index ("3") or a range ("(3, 4)", "[3, 4]" or "3-4")
+///
+fn parse_id(s: &str) -> (usize, usize) {
+    let cleaned = s.trim_matches(|c: char| c == '(' || c == ')' || c == '[' || c == ']' || c.is_whitespace());
+    let parts: Vec<usize> = cleaned
+        .split([',', '-'])
+        .filter_map(|p| p.trim().parse().ok())
+        .collect();
+    match parts.as_slice() {
+        [single] => (*single, *single),
+        [first, .., last] => (*first, *last),
+        _ => (0, 0),
+    }
+}
+
+fn get_string(row: &HashMap<String, String>, key: &str) -> Option<String> {
+    match row.get(key) {
+        Some(v) if !v.is_empty() && v != "_" && v != "None" => Some(v.clone()),
+        _ => None,
+    }
+}
+
+fn get_usize(row: &HashMap<String, String>, key: &str) -> Option<usize> {
+    row.get(key).and_then(|v| v.trim().parse().ok())
+}
@@ -0,0 +1,134 @@
+use std::fmt;
+use std::io;
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::exceptions::{PyFileNotFoundError, PyImportError};
+
+#[cfg(test)]
+mod tests {
+    use crate::error::*;
+
+    #[cfg(feature = "python")]
+    #[test]
+    fn test_module_import_error() {
+        use pyo3::prelude::*;
+
+        let err = Python::with_gil(|py| py.import("rsnltk_missing_module").map(|_| ()).unwrap_err());
+        match Error::from(err) {
+            Error::ModuleImport { module, .. } => assert_eq!(module, "rsnltk_missing_module"),
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        // the interpreter failing to import its own standard library
+        let err = Python::with_gil(|py| py.run("raise ImportError('no module named sys', name='sys')", None, None).unwrap_err());
+        match Error::from(err) {
+            Error::PythonMissing(message) => assert!(message.contains("no module named sys")),
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_io_error() {
+        let err = Error::from(std::fs::File::open("no/such/file.txt").unwrap_err());
+        assert!(matches!(err, Error::Io(_)));
+        assert!(err.to_string().starts_with("IO error: "));
+        assert_eq!(Error::Parse("bad line".to_string()).to_string(), "parse error: bad line");
+    }
+}
+
+///
+/// Errors returned by the `try_*` functions and the Stanza pipeline and session
+///
+#[derive(Debug)]
+pub enum Error {
+    /// The Python interpreter cannot run, e.g. its standard library (`sys`, `encodings`) fails to import
+    PythonMissing(String),
+    /// A Python module such as `stanza`, `nerkit` or `semantickit` could not be imported
+    ModuleImport { module: String, message: String },
+    /// A language model has not been downloaded yet
+    ModelNotDownloaded(String),
+    /// A language or processor is not supported
+    UnsupportedLanguage(String),
+    /// A word is not part of the model vocabulary
+    OutOfVocabulary(String),
+    /// Any other exception raised on the Python side
+    Python(String),
+    Io(io::Error),
+    Parse(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::PythonMissing(msg) => write!(f, "Python is not available: {}", msg),
+            Error::ModuleImport { module, message } => write!(f, "failed to import Python module '{}': {}", module, message),
+            Error::ModelNotDownloaded(msg) => write!(f, "language model not downloaded: {}", msg),
+            Error::UnsupportedLanguage(msg) => write!(f, "unsupported language: {}", msg),
+            Error::OutOfVocabulary(msg) => write!(f, "out of vocabulary: {}", msg),
+            Error::Python(msg) => write!(f, "Python error: {}", msg),
+            Error::Io(err) => write!(f, "IO error: {}", err),
+            Error::Parse(msg) => write!(f, "parse error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+impl From<word2vec::errors::Word2VecError> for Error {
+    fn from(err: word2vec::errors::Word2VecError) -> Error {
+        match err {
+            word2vec::errors::Word2VecError::Io(e) => Error::Io(e),
+            other => Error::Parse(other.to_string()),
+        }
+    }
+}
+
+/// Modules every working interpreter can import; failing to import one means Python itself is broken or missing
+#[cfg(feature = "python")]
+const INTERPRETER_MODULES: [&str; 3] = ["sys", "encodings", "builtins"];
+
+///
+/// Classify a Python exception: a broken interpreter, missing modules, missing Stanza models and unknown languages
+/// get their own variants, everything else becomes `Error::Python`.
+///
+#[cfg(feature = "python")]
+impl From<PyErr> for Error {
+    fn from(err: PyErr) -> Error {
+        Python::with_gil(|py| {
+            let message = err.to_string();
+            let type_name = err.ptype(py).name().unwrap_or("").to_string();
+            if err.is_instance::<PyImportError>(py) {
+                let module = err.pvalue(py).getattr("name").ok()
+                    .and_then(|n| n.extract::<String>().ok())
+                    .unwrap_or_default();
+                if INTERPRETER_MODULES.contains(&module.as_str()) {
+                    Error::PythonMissing(message)
+                } else {
+                    Error::ModuleImport { module, message }
+                }
+            } else if err.is_instance::<PyFileNotFoundError>(py) || type_name == "LanguageNotDownloadedError" {
+                Error::ModelNotDownloaded(message)
+            } else if type_name == "UnknownLanguageError" || type_name == "UnsupportedProcessorError" {
+                Error::UnsupportedLanguage(message)
+            } else {
+                Error::Python(message)
+            }
+        })
+    }
+}
@@ -1,9 +1,17 @@
 extern crate core;
 
+pub mod error;
+pub mod document;
+pub mod conllu;
+pub mod dependency;
+#[cfg(feature = "python")]
 pub mod wordnet;
+#[cfg(feature = "python")]
 pub mod stanza;
 pub mod api;
 pub mod native;
 
+pub use error::{Error, Result};
+#[cfg(feature = "python")]
 pub use stanza::*;
 
@@ -0,0 +1,349 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[cfg(test)]
+mod tests {
+    use crate::native::dictionary::*;
+
+    #[test]
+    fn test_insert_and_contains() {
+        let mut dict = Dictionary::from_words(vec!["苹果", "苹果树", "羽毛球", ""]);
+        assert_eq!(dict.len(), 3);
+        assert!(dict.contains("苹果树"));
+        assert!(!dict.contains("苹"));
+        assert!(!dict.insert("苹果"));
+        assert!(dict.insert("苹"));
+        assert_eq!(dict.len(), 4);
+        assert_eq!(dict.max_word_len(), 3);
+        let words: Vec<String> = dict.entries().into_iter().map(|e| e.0).collect();
+        assert_eq!(words, vec!["羽毛球", "苹", "苹果", "苹果树"]);
+    }
+
+    #[test]
+    fn test_frequency_entries() {
+        let mut dict = Dictionary::new();
+        assert!(dict.insert_entry("苹果", 300, Some("n")));
+        assert!(dict.insert_entry("吃", 100, None));
+        assert!(!dict.insert_entry("苹果", 200, Some("nz")));
+        assert_eq!(dict.freq("苹果"), Some(200));
+        assert_eq!(dict.pos("苹果"), Some("nz"));
+        assert_eq!(dict.total_freq(), 300);
+        assert_eq!(dict.freq("苹"), None);
+        assert!(dict.remove("吃"));
+        assert!(!dict.remove("吃"));
+        assert_eq!((dict.len(), dict.total_freq()), (1, 200));
+        assert_eq!(dict.longest_suffix(&["吃"], 1), None);
+        assert_eq!(parse_entry("苹果树 12 n"), Some(("苹果树".to_string(), Some(12), Some("n".to_string()))));
+        assert_eq!(parse_entry("ice cream"), Some(("ice cream".to_string(), None, None)));
+    }
+
+    #[test]
+    fn test_prefix_and_suffix_matches() {
+        let dict = Dictionary::from_words(vec!["苹", "苹果", "苹果树", "果树"]);
+        let text = ["苹", "果", "树", "下"];
+        assert_eq!(dict.prefix_matches(&text), vec![1, 2, 3]);
+        assert_eq!(dict.longest_prefix(&text, 2), Some(2));
+        assert_eq!(dict.longest_suffix(&text[..3], 4), Some(3));
+        assert_eq!(dict.longest_suffix(&text[..3], 2), Some(2));
+        assert_eq!(dict.longest_suffix(&text, 4), None);
+    }
+}
+
+///
+/// A word dictionary stored as a pair of character tries, one for forward and one for backward matching.
+/// Loading is done once; lookups only walk as far as the text matches, whatever the dictionary size.
+///
+#[derive(Debug, Clone, Default)]
+pub struct Dictionary {
+    forward: Trie,
+    backward: Trie,
+    len: usize,
+    max_word_len: usize,
+    total_freq: u64,
+}
+
+impl Dictionary {
+    pub fn new() -> Dictionary {
+        Dictionary::default()
+    }
+
+    ///
+    /// Build a dictionary from a list of words, empty entries are skipped
+    ///
+    pub fn from_words<I, S>(words: I) -> Dictionary
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut dict = Dictionary::new();
+        for word in words {
+            dict.insert(word.as_ref());
+        }
+        dict
+    }
+
+    ///
+    /// Load a dictionary file where each line is a term, optionally followed by its frequency
+    /// and part-of-speech tag as in "苹果 300 n"
+    ///
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Dictionary> {
+        let mut dict = Dictionary::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            if let Some((word, freq, pos)) = parse_entry(&line?) {
+                dict.insert_entry(&word, freq.unwrap_or(1), pos.as_deref());
+            }
+        }
+        Ok(dict)
+    }
+
+    ///
+    /// Add a word with frequency 1, returns false when it was already present or empty
+    ///
+    pub fn insert(&mut self, word: &str) -> bool {
+        if self.contains(word) {
+            return false;
+        }
+        self.insert_entry(word, 1, None)
+    }
+
+    ///
+    /// Add a word or update the frequency and part-of-speech tag of a known one.
+    /// Returns true when the word is new.
+    ///
+    pub fn insert_entry(&mut self, word: &str, freq: u64, pos: Option<&str>) -> bool {
+        if word.is_empty() {
+            return false;
+        }
+        let node = self.forward.insert(word.chars());
+        let entry = &mut self.forward.nodes[node];
+        let added = !entry.is_word;
+        if added {
+            let last = self.backward.insert(word.chars().rev());
+            self.backward.nodes[last].is_word = true;
+            self.len += 1;
+            self.max_word_len = self.max_word_len.max(word.graphemes(true).count());
+        } else {
+            self.total_freq -= entry.freq;
+        }
+        entry.is_word = true;
+        entry.freq = freq;
+        entry.pos = pos.map(String::from);
+        self.total_freq += freq;
+        added
+    }
+
+    ///
+    /// Remove a word, returns false when it was not present.
+    /// `max_word_len` is kept as it was, so it stays an upper bound.
+    ///
+    pub fn remove(&mut self, word: &str) -> bool {
+        let node = match self.forward.find(word.chars()) {
+            Some(node) if !word.is_empty() && self.forward.nodes[node].is_word => node,
+            _ => return false,
+        };
+        let entry = &mut self.forward.nodes[node];
+        entry.is_word = false;
+        self.total_freq -= entry.freq;
+        entry.freq = 0;
+        entry.pos = None;
+        if let Some(last) = self.backward.find(word.chars().rev()) {
+            self.backward.nodes[last].is_word = false;
+        }
+        self.len -= 1;
+        true
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.entry(word).is_some()
+    }
+
+    pub fn freq(&self, word: &str) -> Option<u64> {
+        self.entry(word).map(|n| n.freq)
+    }
+
+    pub fn pos(&self, word: &str) -> Option<&str> {
+        self.entry(word).and_then(|n| n.pos.as_deref())
+    }
+
+    ///
+    /// All entries as (word, frequency, part-of-speech tag), in character order
+    ///
+    pub fn entries(&self) -> Vec<(String, u64, Option<String>)> {
+        let mut entries = Vec::with_capacity(self.len);
+        let mut stack: Vec<(usize, String)> = vec![(0, String::new())];
+        while let Some((node, word)) = stack.pop() {
+            let n = &self.forward.nodes[node];
+            if n.is_word {
+                entries.push((word.clone(), n.freq, n.pos.clone()));
+            }
+            for &(c, child) in n.children.iter().rev() {
+                let mut next = word.clone();
+                next.push(c);
+                stack.push((child, next));
+            }
+        }
+        entries
+    }
+
+    ///
+    /// Sum of the frequencies of all entries
+    ///
+    pub fn total_freq(&self) -> u64 {
+        self.total_freq
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    ///
+    /// Length of the longest entry in graphemes
+    ///
+    pub fn max_word_len(&self) -> usize {
+        self.max_word_len
+    }
+
+    ///
+    /// Lengths, in graphemes, of all dictionary words starting the grapheme slice, shortest first
+    ///
+    pub fn prefix_matches(&self, graphemes: &[&str]) -> Vec<usize> {
+        self.prefix_freqs(graphemes).into_iter().map(|(len, _)| len).collect()
+    }
+
+    ///
+    /// Lengths and frequencies of all dictionary words starting the grapheme slice, shortest first
+    ///
+    pub fn prefix_freqs(&self, graphemes: &[&str]) -> Vec<(usize, u64)> {
+        let mut matches = Vec::new();
+        self.forward.walk(graphemes.iter().map(|g| g.chars()), |n, node| matches.push((n, self.forward.nodes[node].freq)));
+        matches
+    }
+
+    ///
+    /// Length of the longest dictionary word starting the grapheme slice, at most `max_len` graphemes
+    ///
+    pub fn longest_prefix(&self, graphemes: &[&str], max_len: usize) -> Option<usize> {
+        let mut longest = None;
+        let end = max_len.min(graphemes.len());
+        self.forward.walk(graphemes[..end].iter().map(|g| g.chars()), |n, _| longest = Some(n));
+        longest
+    }
+
+    ///
+    /// Length of the longest dictionary word ending the grapheme slice, at most `max_len` graphemes
+    ///
+    pub fn longest_suffix(&self, graphemes: &[&str], max_len: usize) -> Option<usize> {
+        let mut longest = None;
+        let start = graphemes.len() - max_len.min(graphemes.len());
+        self.backward.walk(graphemes[start..].iter().rev().map(|g| g.chars().rev()), |n, _| longest = Some(n));
+        longest
+    }
+
+    fn entry(&self, word: &str) -> Option<&Node> {
+        if word.is_empty() {
+            return None;
+        }
+        self.forward.find(word.chars()).map(|n| &self.forward.nodes[n]).filter(|n| n.is_word)
+    }
+}
+
+///
+/// Parse a dictionary line "word [freq [pos]]". A line without a numeric second field is a single
+/// term without frequency, so multi-word terms like "ice cream" are kept whole. Blank lines give None.
+///
+pub fn parse_entry(line: &str) -> Option<(String, Option<u64>, Option<String>)> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    match fields.as_slice() {
+        [word, freq] | [word, freq, _] => match freq.parse() {
+            Ok(freq) => Some((word.to_string(), Some(freq), fields.get(2).map(|p| p.to_string()))),
+            Err(_) => Some((line.to_string(), None, None)),
+        },
+        _ => Some((line.to_string(), None, None)),
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Trie {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    /// Sorted by character for binary search
+    children: Vec<(char, usize)>,
+    is_word: bool,
+    freq: u64,
+    pos: Option<String>,
+}
+
+impl Default for Trie {
+    fn default() -> Trie {
+        Trie { nodes: vec![Node::default()] }
+    }
+}
+
+impl Trie {
+    fn child(&self, node: usize, c: char) -> Option<usize> {
+        let children = &self.nodes[node].children;
+        children.binary_search_by_key(&c, |&(k, _)| k).ok().map(|i| children[i].1)
+    }
+
+    fn find<I: Iterator<Item = char>>(&self, chars: I) -> Option<usize> {
+        let mut node = 0;
+        for c in chars {
+            node = self.child(node, c)?;
+        }
+        Some(node)
+    }
+
+    /// Returns the node of the last character, created if needed
+    fn insert<I: Iterator<Item = char>>(&mut self, chars: I) -> usize {
+        let mut node = 0;
+        for c in chars {
+            node = match self.nodes[node].children.binary_search_by_key(&c, |&(k, _)| k) {
+                Ok(i) => self.nodes[node].children[i].1,
+                Err(i) => {
+                    let next = self.nodes.len();
+                    self.nodes.push(Node::default());
+                    self.nodes[node].children.insert(i, (c, next));
+                    next
+                }
+            };
+        }
+        node
+    }
+
+    ///
+    /// Follow a sequence of graphemes (each given as its characters) and call `on_word`
+    /// with the number of graphemes consumed and the node every time a word ends on a grapheme boundary
+    ///
+    fn walk<G, C, F>(&self, graphemes: G, mut on_word: F)
+    where
+        G: Iterator<Item = C>,
+        C: Iterator<Item = char>,
+        F: FnMut(usize, usize),
+    {
+        let mut node = 0;
+        for (i, chars) in graphemes.enumerate() {
+            for c in chars {
+                node = match self.child(node, c) {
+                    Some(next) => next,
+                    None => return,
+                };
+            }
+            if self.nodes[node].is_word {
+                on_word(i + 1, node);
+            }
+        }
+    }
+}
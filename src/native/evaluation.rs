@@ -0,0 +1,186 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use unicode_segmentation::UnicodeSegmentation;
+use crate::error::{Error, Result};
+use crate::native::dictionary::Dictionary;
+
+#[cfg(test)]
+mod tests {
+    use crate::native::dictionary::Dictionary;
+    use crate::native::evaluation::*;
+
+    fn words(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let vocabulary = Dictionary::from_words(vec!["我", "喜欢", "北京"]);
+        let gold = vec![words("我 喜欢 北京"), words("李明 喜欢 我")];
+        let predicted = vec![words("我 喜欢 北京"), words("李 明 喜欢 我")];
+        let evaluation = evaluate(&gold, &predicted, &vocabulary).unwrap();
+        let score = &evaluation.score;
+        assert_eq!((score.gold_words, score.predicted_words, score.correct_words), (6, 7, 5));
+        assert!((score.precision - 5.0 / 7.0).abs() < 1e-9);
+        assert!((score.recall - 5.0 / 6.0).abs() < 1e-9);
+        assert_eq!(score.iv_recall, 1.0);
+        assert_eq!(score.oov_recall, 0.0);
+        assert!((score.oov_rate - 1.0 / 6.0).abs() < 1e-9);
+        assert_eq!(evaluation.errors.len(), 1);
+        assert_eq!(evaluation.errors[0].index, 1);
+        assert_eq!(evaluation.errors[0].missed, vec!["李明"]);
+        assert_eq!(evaluation.errors[0].spurious, vec!["李", "明"]);
+    }
+
+    #[test]
+    fn test_mismatched_input() {
+        let vocabulary = Dictionary::new();
+        assert!(evaluate(&[words("我 喜欢")], &Vec::<Vec<String>>::new(), &vocabulary).is_err());
+        assert!(evaluate(&[words("我 喜欢")], &[words("我 喜")], &vocabulary).is_err());
+    }
+}
+
+///
+/// Word-level scores of a segmentation against the gold standard, as reported by the SIGHAN bakeoffs.
+/// A word is in vocabulary (IV) when it is in the dictionary the segmenter was given, otherwise out of vocabulary (OOV).
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SegmentationScore {
+    pub gold_words: usize,
+    pub predicted_words: usize,
+    pub correct_words: usize,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+    /// Share of gold words out of vocabulary
+    pub oov_rate: f64,
+    pub oov_recall: f64,
+    pub iv_recall: f64,
+}
+
+///
+/// Words of a sentence that were segmented differently from the gold standard
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SentenceErrors {
+    /// Position of the sentence in the input
+    pub index: usize,
+    pub gold: Vec<String>,
+    pub predicted: Vec<String>,
+    /// Gold words the segmenter did not produce
+    pub missed: Vec<String>,
+    /// Predicted words not in the gold standard
+    pub spurious: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Evaluation {
+    pub score: SegmentationScore,
+    /// Only sentences with at least one error
+    pub errors: Vec<SentenceErrors>,
+}
+
+///
+/// Compare predicted segmentations with gold ones, sentence by sentence.
+/// Both lists must have the same length and each pair of sentences the same text.
+///
+pub fn evaluate<G, P>(gold: &[Vec<G>], predicted: &[Vec<P>], vocabulary: &Dictionary) -> Result<Evaluation>
+where
+    G: AsRef<str>,
+    P: AsRef<str>,
+{
+    if gold.len() != predicted.len() {
+        return Err(Error::Parse(format!("{} gold sentences but {} predicted", gold.len(), predicted.len())));
+    }
+    let mut score = SegmentationScore::default();
+    let (mut iv_words, mut iv_correct, mut oov_words, mut oov_correct) = (0, 0, 0, 0);
+    let mut errors = Vec::new();
+    for (index, (g, p)) in gold.iter().zip(predicted).enumerate() {
+        let gold_text: String = g.iter().map(|w| w.as_ref()).collect();
+        let predicted_text: String = p.iter().map(|w| w.as_ref()).collect();
+        if gold_text != predicted_text {
+            return Err(Error::Parse(format!("sentence {} differs: {:?} vs {:?}", index, gold_text, predicted_text)));
+        }
+        let gold_spans = spans(g);
+        let predicted_spans = spans(p);
+        let predicted_set: HashSet<(usize, usize)> = predicted_spans.iter().map(|s| s.0).collect();
+        let gold_set: HashSet<(usize, usize)> = gold_spans.iter().map(|s| s.0).collect();
+
+        let mut missed = Vec::new();
+        for (span, word) in &gold_spans {
+            let correct = predicted_set.contains(span);
+            if vocabulary.contains(word) {
+                iv_words += 1;
+                iv_correct += correct as usize;
+            } else {
+                oov_words += 1;
+                oov_correct += correct as usize;
+            }
+            if correct {
+                score.correct_words += 1;
+            } else {
+                missed.push(word.to_string());
+            }
+        }
+        let spurious: Vec<String> = predicted_spans.iter().filter(|(span, _)| !gold_set.contains(span)).map(|(_, w)| w.to_string()).collect();
+        score.gold_words += gold_spans.len();
+        score.predicted_words += predicted_spans.len();
+        if !missed.is_empty() || !spurious.is_empty() {
+            errors.push(SentenceErrors {
+                index,
+                gold: g.iter().map(|w| w.as_ref().to_string()).collect(),
+                predicted: p.iter().map(|w| w.as_ref().to_string()).collect(),
+                missed,
+                spurious,
+            });
+        }
+    }
+    score.precision = ratio(score.correct_words, score.predicted_words);
+    score.recall = ratio(score.correct_words, score.gold_words);
+    score.f1 = if score.precision + score.recall > 0.0 {
+        2.0 * score.precision * score.recall / (score.precision + score.recall)
+    } else {
+        0.0
+    };
+    score.oov_rate = ratio(oov_words, score.gold_words);
+    score.oov_recall = ratio(oov_correct, oov_words);
+    score.iv_recall = ratio(iv_correct, iv_words);
+    Ok(Evaluation { score, errors })
+}
+
+///
+/// Evaluate two segmented files where each line is a sentence and words are separated by spaces
+///
+pub fn evaluate_files<P: AsRef<Path>, Q: AsRef<Path>>(gold_path: P, predicted_path: Q, vocabulary: &Dictionary) -> Result<Evaluation> {
+    let read = |text: String| -> Vec<Vec<String>> {
+        text.lines().map(|l| l.split_whitespace().map(String::from).collect()).collect()
+    };
+    let gold = read(fs::read_to_string(gold_path)?);
+    let predicted = read(fs::read_to_string(predicted_path)?);
+    evaluate(&gold, &predicted, vocabulary)
+}
+
+///
+/// Grapheme span of every word, with the word
+///
+fn spans<W: AsRef<str>>(words: &[W]) -> Vec<((usize, usize), &str)> {
+    let mut start = 0;
+    words
+        .iter()
+        .map(|w| {
+            let end = start + w.as_ref().graphemes(true).count();
+            let span = ((start, end), w.as_ref());
+            start = end;
+            span
+        })
+        .collect()
+}
+
+fn ratio(a: usize, b: usize) -> f64 {
+    if b == 0 {
+        0.0
+    } else {
+        a as f64 / b as f64
+    }
+}
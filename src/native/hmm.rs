@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use crate::error::{Error, Result};
+
+#[cfg(test)]
+mod tests {
+    use crate::native::hmm::*;
+
+    const MODEL: &str = "# toy BMES model
+start\tB\t-0.5
+start\tS\t-1.0
+trans\tB\tE\t-0.3
+trans\tB\tM\t-1.5
+trans\tM\tE\t-0.4
+trans\tM\tM\t-1.2
+trans\tE\tB\t-0.6
+trans\tE\tS\t-0.8
+trans\tS\tB\t-0.5
+trans\tS\tS\t-1.0
+emit\tB\t李\t-1.0
+emit\tE\t明\t-1.0
+emit\tE\t华\t-1.5
+emit\tS\t和\t-0.5
+emit\tB\t小\t-1.0
+";
+
+    #[test]
+    fn test_parse_and_viterbi() {
+        let hmm = Hmm::parse(MODEL).unwrap();
+        assert_eq!(hmm.states(), &["B", "M", "E", "S"]);
+        assert_eq!(hmm.viterbi(&["李", "明"]), vec!["B", "E"]);
+        assert_eq!(hmm.cut(&["李", "明", "和", "小", "华"]), vec!["李明", "和", "小华"]);
+        assert!(hmm.viterbi(&[]).is_empty());
+        assert_eq!(Hmm::parse(&hmm.to_string()).unwrap(), hmm);
+    }
+
+    #[test]
+    fn test_viterbi_with_known() {
+        let tags = Hmm::parse_with_states("start\tr\t-0.1\nstart\tv\t-3\nstart\tn\t-3\ntrans\tr\tv\t-0.2\ntrans\tv\tn\t-0.3\n\
+trans\tv\tv\t-2\ntrans\tn\tv\t-1\nemit\tn\t<unk>\t-2\nemit\tv\t<unk>\t-5\nemit\tr\t<unk>\t-9\n", &[]).unwrap();
+        assert_eq!(tags.states(), &["r", "v", "n"]);
+        // "我 喜欢 李明": the unknown last word follows a verb, so it is a noun
+        assert_eq!(tags.viterbi_with_known(&["我", "喜欢", "李明"], &[Some("r"), Some("v"), None]), vec!["r", "v", "n"]);
+        assert_eq!(tags.viterbi_with_known(&["我", "x"], &[Some("r"), Some("eng")]), vec!["r", "eng"]);
+    }
+
+    #[test]
+    fn test_merge_single_graphemes() {
+        let hmm = Hmm::parse(MODEL).unwrap();
+        let words: Vec<String> = ["我", "喜欢", "李", "明", "和", "小", "华"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(hmm.merge_single_graphemes(words), vec!["我", "喜欢", "李明", "和", "小华"]);
+
+        // punctuation next to an unknown word is never joined to it
+        let words: Vec<String> = ["我", "喜欢", "李", "明", "，", "李", "。"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(hmm.merge_single_graphemes(words), vec!["我", "喜欢", "李明", "，", "李", "。"]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Hmm::parse("start\tB\tnot-a-number").is_err());
+        assert!(Hmm::parse("emit\tB").is_err());
+        assert!(Hmm::parse("unknown\tB\t-1").is_err());
+    }
+}
+
+/// Log probability used for transitions and emissions missing from the model
+pub const MIN_LOG_PROB: f64 = -3.14e100;
+
+/// Observation whose emission probabilities are used for observations missing from the model
+pub const UNKNOWN_OBSERVATION: &str = "<unk>";
+
+/// States of the character tagging model used for segmentation: Begin, Middle, End of a word, Single-character word
+pub const BMES: [&str; 4] = ["B", "M", "E", "S"];
+
+///
+/// A hidden Markov model with log probabilities, decoded with the Viterbi algorithm.
+/// With the BMES states it tags characters to discover words missing from the dictionary.
+///
+/// The model file has one tab-separated entry per line, "#" starts a comment:
+///
+/// - `start\tB\t-0.26`: initial log probability of a state,
+/// - `trans\tB\tE\t-0.51`: log probability of moving from the first state to the second,
+/// - `emit\tB\t中\t-8.12`: log probability of a state emitting an observation.
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Hmm {
+    states: Vec<String>,
+    start: Vec<f64>,
+    trans: Vec<Vec<f64>>,
+    emit: Vec<HashMap<String, f64>>,
+}
+
+impl Hmm {
+    ///
+    /// An empty model over the given states, every probability set to `MIN_LOG_PROB`
+    ///
+    pub fn new(states: &[&str]) -> Hmm {
+        let n = states.len();
+        Hmm {
+            states: states.iter().map(|s| s.to_string()).collect(),
+            start: vec![MIN_LOG_PROB; n],
+            trans: vec![vec![MIN_LOG_PROB; n]; n],
+            emit: vec![HashMap::new(); n],
+        }
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Hmm> {
+        Hmm::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn from_file_with_states<P: AsRef<Path>>(path: P, states: &[&str]) -> Result<Hmm> {
+        Hmm::parse_with_states(&fs::read_to_string(path)?, states)
+    }
+
+    ///
+    /// Parse a model in the format described above. States are the BMES states followed by any other state found in the text.
+    /// Lines that do not start with "start", "trans" or "emit" are errors.
+    ///
+    pub fn parse(input: &str) -> Result<Hmm> {
+        Hmm::parse_with_states(input, &BMES)
+    }
+
+    ///
+    /// Parse a model whose states are `states` followed by the other states found in the text,
+    /// e.g. no predefined state for a part-of-speech model
+    ///
+    pub fn parse_with_states(input: &str, states: &[&str]) -> Result<Hmm> {
+        let mut hmm = Hmm::new(states);
+        for (n, line) in input.lines().enumerate() {
+            hmm.parse_line(line).map_err(|msg| Error::Parse(format!("HMM line {}: {}", n + 1, msg)))?;
+        }
+        Ok(hmm)
+    }
+
+    ///
+    /// Read one line of a model file, skipping blank lines and comments
+    ///
+    pub(crate) fn parse_line(&mut self, line: &str) -> std::result::Result<(), String> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.trim().is_empty() || line.starts_with('#') {
+            return Ok(());
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+        let prob = |s: &str| s.trim().parse::<f64>().map_err(|_| format!("invalid log probability {:?}", s));
+        match fields.as_slice() {
+            ["start", state, p] => self.set_start(state, prob(p)?),
+            ["trans", from, to, p] => self.set_trans(from, to, prob(p)?),
+            ["emit", state, observation, p] => self.set_emit(state, observation, prob(p)?),
+            _ => return Err(format!("expected start, trans or emit entry, got {:?}", line)),
+        }
+        Ok(())
+    }
+
+    pub fn states(&self) -> &[String] {
+        &self.states
+    }
+
+    pub fn set_start(&mut self, state: &str, log_prob: f64) {
+        let s = self.state_index(state);
+        self.start[s] = log_prob;
+    }
+
+    pub fn set_trans(&mut self, from: &str, to: &str, log_prob: f64) {
+        let (from, to) = (self.state_index(from), self.state_index(to));
+        self.trans[from][to] = log_prob;
+    }
+
+    pub fn set_emit(&mut self, state: &str, observation: &str, log_prob: f64) {
+        let s = self.state_index(state);
+        self.emit[s].insert(observation.to_string(), log_prob);
+    }
+
+    ///
+    /// Most likely state sequence for the observations
+    ///
+    pub fn viterbi(&self, observations: &[&str]) -> Vec<String> {
+        let end_states: Vec<usize> = (0..self.states.len()).collect();
+        self.decode(observations, &end_states, &[]).into_iter().map(|s| self.states[s].clone()).collect()
+    }
+
+    ///
+    /// Most likely state sequence when some states are already known, e.g. the part-of-speech tags of dictionary words.
+    /// A known state missing from the model does not constrain decoding but is kept in the output.
+    ///
+    pub fn viterbi_with_known(&self, observations: &[&str], known: &[Option<&str>]) -> Vec<String> {
+        let end_states: Vec<usize> = (0..self.states.len()).collect();
+        let fixed: Vec<Option<usize>> = known
+            .iter()
+            .map(|k| k.and_then(|k| self.states.iter().position(|s| s == k)))
+            .collect();
+        self.decode(observations, &end_states, &fixed)
+            .into_iter()
+            .enumerate()
+            .map(|(i, s)| match known.get(i).copied().flatten() {
+                Some(k) => k.to_string(),
+                None => self.states[s].clone(),
+            })
+            .collect()
+    }
+
+    ///
+    /// Split graphemes into words with the BMES states: a word ends at every E or S
+    ///
+    pub fn cut(&self, graphemes: &[&str]) -> Vec<String> {
+        let end_states: Vec<usize> = ["E", "S"].iter().filter_map(|s| self.states.iter().position(|x| x == s)).collect();
+        let tags = self.decode(graphemes, &end_states, &[]);
+        let mut words = Vec::new();
+        let mut begin = 0;
+        for (i, &tag) in tags.iter().enumerate() {
+            let name = self.states[tag].as_str();
+            if name == "E" || name == "S" || i + 1 == graphemes.len() {
+                words.push(graphemes[begin..=i].concat());
+                begin = i + 1;
+            }
+        }
+        words
+    }
+
+    ///
+    /// Re-segment every run of two or more single-grapheme words with `cut`, keeping the other words.
+    /// Used after dictionary segmentation to join the characters of unknown words.
+    /// Only word characters (Han, kana, letters, digits) are joined; punctuation and whitespace end a run.
+    ///
+    pub fn merge_single_graphemes(&self, words: Vec<String>) -> Vec<String> {
+        use unicode_segmentation::UnicodeSegmentation;
+
+        let mut merged = Vec::with_capacity(words.len());
+        let mut run: Vec<String> = Vec::new();
+        for word in words {
+            if word.graphemes(true).count() == 1 && word.chars().all(char::is_alphanumeric) {
+                run.push(word);
+                continue;
+            }
+            self.flush_run(&mut run, &mut merged);
+            merged.push(word);
+        }
+        self.flush_run(&mut run, &mut merged);
+        merged
+    }
+
+    fn flush_run(&self, run: &mut Vec<String>, merged: &mut Vec<String>) {
+        if run.len() > 1 {
+            let graphemes: Vec<&str> = run.iter().map(|s| s.as_str()).collect();
+            merged.extend(self.cut(&graphemes));
+        } else {
+            merged.append(run);
+        }
+        run.clear();
+    }
+
+    fn state_index(&mut self, state: &str) -> usize {
+        if let Some(i) = self.states.iter().position(|s| s == state) {
+            return i;
+        }
+        self.states.push(state.to_string());
+        self.start.push(MIN_LOG_PROB);
+        for row in self.trans.iter_mut() {
+            row.push(MIN_LOG_PROB);
+        }
+        self.trans.push(vec![MIN_LOG_PROB; self.states.len()]);
+        self.emit.push(HashMap::new());
+        self.states.len() - 1
+    }
+
+    ///
+    /// Unseen observations fall back to the `UNKNOWN_OBSERVATION` entry of the state when the model has one
+    ///
+    fn emission(&self, state: usize, observation: &str) -> f64 {
+        let emit = &self.emit[state];
+        emit.get(observation).or_else(|| emit.get(UNKNOWN_OBSERVATION)).copied().unwrap_or(MIN_LOG_PROB)
+    }
+
+    ///
+    /// Viterbi decoding, the last state is taken from `end_states` and `fixed` forces the state of some positions
+    ///
+    fn decode(&self, observations: &[&str], end_states: &[usize], fixed: &[Option<usize>]) -> Vec<usize> {
+        let n = self.states.len();
+        if observations.is_empty() || n == 0 {
+            return Vec::new();
+        }
+        let allowed = |t: usize, s: usize| match fixed.get(t).copied().flatten() {
+            Some(k) => k == s,
+            None => true,
+        };
+        let mut scores: Vec<Vec<f64>> = vec![(0..n)
+            .map(|s| if allowed(0, s) { self.start[s] + self.emission(s, observations[0]) } else { f64::NEG_INFINITY })
+            .collect()];
+        let mut back: Vec<Vec<usize>> = vec![vec![0; n]];
+        for (t, observation) in observations.iter().enumerate().skip(1) {
+            let prev = scores.last().unwrap();
+            let mut row = Vec::with_capacity(n);
+            let mut pointers = Vec::with_capacity(n);
+            for s in 0..n {
+                let (best_prev, best) = (0..n)
+                    .map(|p| (p, prev[p] + self.trans[p][s]))
+                    .fold((0, f64::NEG_INFINITY), |a, b| if b.1 > a.1 { b } else { a });
+                row.push(if allowed(t, s) { best + self.emission(s, observation) } else { f64::NEG_INFINITY });
+                pointers.push(best_prev);
+            }
+            scores.push(row);
+            back.push(pointers);
+        }
+        let last = scores.last().unwrap();
+        let mut state = end_states
+            .iter()
+            .copied()
+            .fold(None, |best: Option<usize>, s| match best {
+                Some(b) if last[b] >= last[s] => Some(b),
+                _ => Some(s),
+            })
+            .unwrap_or(0);
+        let mut path = vec![state; observations.len()];
+        for t in (1..observations.len()).rev() {
+            state = back[t][state];
+            path[t - 1] = state;
+        }
+        path
+    }
+}
+
+///
+/// Write the model in the file format read by `Hmm::parse`, leaving out entries at `MIN_LOG_PROB`
+///
+impl fmt::Display for Hmm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (s, state) in self.states.iter().enumerate() {
+            if self.start[s] > MIN_LOG_PROB {
+                writeln!(f, "start\t{}\t{}", state, self.start[s])?;
+            }
+        }
+        for (from, row) in self.trans.iter().enumerate() {
+            for (to, &p) in row.iter().enumerate() {
+                if p > MIN_LOG_PROB {
+                    writeln!(f, "trans\t{}\t{}\t{}", self.states[from], self.states[to], p)?;
+                }
+            }
+        }
+        for (s, emissions) in self.emit.iter().enumerate() {
+            let mut observations: Vec<(&String, &f64)> = emissions.iter().collect();
+            observations.sort_by(|a, b| a.0.cmp(b.0));
+            for (observation, p) in observations {
+                writeln!(f, "emit\t{}\t{}\t{}", self.states[s], observation, p)?;
+            }
+        }
+        Ok(())
+    }
+}
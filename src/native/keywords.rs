@@ -0,0 +1,353 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use unicode_segmentation::UnicodeSegmentation;
+use crate::error::{Error, Result};
+
+#[cfg(test)]
+mod tests {
+    use crate::native::keywords::*;
+
+    const TEXT: &str = "Rust is a systems programming language. Rust guarantees memory safety. \
+        The borrow checker of Rust validates references, and memory is freed without a garbage collector.";
+
+    fn config() -> KeywordConfig {
+        KeywordConfig { top_k: 3, ..Default::default() }.stop_words(vec!["is", "a", "the", "of", "and", "without"])
+    }
+
+    #[test]
+    fn test_idf_table() {
+        let docs = vec![split_words("rust memory"), split_words("rust borrow"), split_words("python")];
+        let idf = IdfTable::from_documents(&docs);
+        assert_eq!(idf.len(), 4);
+        assert!(idf.idf("borrow") > idf.idf("rust"));
+        assert_eq!(idf.idf("unseen"), idf.default_idf());
+        let parsed = IdfTable::parse(&idf.to_string()).unwrap();
+        assert!((parsed.idf("memory") - idf.idf("memory")).abs() < 1e-9);
+        assert!(IdfTable::parse("rust high").is_err());
+    }
+
+    #[test]
+    fn test_tf_idf() {
+        let words = split_words(TEXT);
+        let mut idf = IdfTable::new();
+        idf.set("rust", 0.1);
+        idf.set("memory", 2.0);
+        let keywords = tf_idf(&words, &idf, &config());
+        assert_eq!(keywords.len(), 3);
+        assert_eq!(keywords[0].word, "memory");
+        assert!(keywords.iter().all(|k| k.word != "is"));
+    }
+
+    #[test]
+    fn test_text_rank() {
+        let words = split_words(TEXT);
+        let keywords = text_rank(&words, &config());
+        assert_eq!(keywords[0].word, "rust");
+        assert_eq!(keywords[0].score, 1.0);
+
+        // only nouns are kept
+        let tagged = vec![("我", "r"), ("喜欢", "v"), ("北京", "ns"), ("天安门", "ns"), ("北京", "ns"), ("故宫", "ns")];
+        let config = KeywordConfig { allowed_pos: vec!["ns".to_string()], ..Default::default() };
+        let keywords = text_rank_tagged(&tagged, &config);
+        let words: Vec<&str> = keywords.iter().map(|k| k.word.as_str()).collect();
+        assert_eq!(words[0], "北京");
+        assert_eq!(words.len(), 3);
+    }
+}
+
+///
+/// Inverse document frequencies of words, used by `tf_idf`.
+/// Words missing from the table get `default_idf`, the median of the table unless set.
+///
+/// The table file holds one word and its IDF per line, separated by whitespace, as the `idf.txt` of jieba.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdfTable {
+    idf: HashMap<String, f64>,
+    default_idf: f64,
+}
+
+impl Default for IdfTable {
+    fn default() -> IdfTable {
+        IdfTable { idf: HashMap::new(), default_idf: 1.0 }
+    }
+}
+
+impl IdfTable {
+    pub fn new() -> IdfTable {
+        IdfTable::default()
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<IdfTable> {
+        IdfTable::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(input: &str) -> Result<IdfTable> {
+        let mut table = IdfTable::new();
+        for (n, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parsed = line.rsplit_once(char::is_whitespace).and_then(|(word, idf)| Some((word.trim(), idf.parse().ok()?)));
+            match parsed {
+                Some((word, idf)) if !word.is_empty() => table.idf.insert(word.to_string(), idf),
+                _ => return Err(Error::Parse(format!("idf line {}: expected word and idf, got {:?}", n + 1, line))),
+            };
+        }
+        table.default_idf = table.median();
+        Ok(table)
+    }
+
+    ///
+    /// Compute the IDF of every word of a corpus of segmented documents,
+    /// smoothed as ln((1 + documents) / (1 + documents with the word)) + 1
+    ///
+    pub fn from_documents<W: AsRef<str>>(documents: &[Vec<W>]) -> IdfTable {
+        let mut df: HashMap<&str, usize> = HashMap::new();
+        for doc in documents {
+            let unique: HashSet<&str> = doc.iter().map(|w| w.as_ref()).collect();
+            for word in unique {
+                *df.entry(word).or_insert(0) += 1;
+            }
+        }
+        let n = documents.len() as f64;
+        let idf = df.into_iter().map(|(word, count)| (word.to_string(), ((1.0 + n) / (1.0 + count as f64)).ln() + 1.0)).collect();
+        let mut table = IdfTable { idf, default_idf: 1.0 };
+        table.default_idf = table.median();
+        table
+    }
+
+    pub fn write_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    pub fn idf(&self, word: &str) -> f64 {
+        self.idf.get(word).copied().unwrap_or(self.default_idf)
+    }
+
+    pub fn set(&mut self, word: &str, idf: f64) {
+        self.idf.insert(word.to_string(), idf);
+    }
+
+    pub fn default_idf(&self) -> f64 {
+        self.default_idf
+    }
+
+    pub fn set_default_idf(&mut self, idf: f64) {
+        self.default_idf = idf;
+    }
+
+    pub fn len(&self) -> usize {
+        self.idf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.idf.is_empty()
+    }
+
+    fn median(&self) -> f64 {
+        let mut values: Vec<f64> = self.idf.values().copied().collect();
+        if values.is_empty() {
+            return 1.0;
+        }
+        values.sort_by(|a, b| a.total_cmp(b));
+        values[values.len() / 2]
+    }
+}
+
+impl fmt::Display for IdfTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut words: Vec<(&String, &f64)> = self.idf.iter().collect();
+        words.sort_by(|a, b| a.0.cmp(b.0));
+        for (word, idf) in words {
+            writeln!(f, "{} {}", word, idf)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyword {
+    pub word: String,
+    pub score: f64,
+}
+
+///
+/// Settings of the keyword extractors
+///
+/// - `top_k`: number of keywords returned, 0 for all,
+/// - `stop_words`: words never returned, compared after lower-casing,
+/// - `allowed_pos`: part-of-speech tags of the words kept by the `*_tagged` functions, e.g. "n", "ns", "vn"; empty for all,
+/// - `min_word_len`: shortest keyword in graphemes, so that single characters can be left out,
+/// - `window`: number of consecutive words linked together in the TextRank graph.
+///
+/// Words without any letter or digit, such as punctuation, are always left out.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeywordConfig {
+    pub top_k: usize,
+    pub stop_words: HashSet<String>,
+    pub allowed_pos: Vec<String>,
+    pub min_word_len: usize,
+    pub window: usize,
+}
+
+impl Default for KeywordConfig {
+    fn default() -> KeywordConfig {
+        KeywordConfig { top_k: 10, stop_words: HashSet::new(), allowed_pos: Vec::new(), min_word_len: 1, window: 5 }
+    }
+}
+
+impl KeywordConfig {
+    pub fn stop_words<I, S>(mut self, words: I) -> KeywordConfig
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.stop_words.extend(words.into_iter().map(|w| w.as_ref().to_lowercase()));
+        self
+    }
+
+    fn accepts(&self, word: &str, pos: Option<&str>) -> bool {
+        if !self.allowed_pos.is_empty() && !pos.map(|p| self.allowed_pos.iter().any(|a| a == p)).unwrap_or(false) {
+            return false;
+        }
+        word.chars().any(char::is_alphanumeric)
+            && word.graphemes(true).count() >= self.min_word_len
+            && !self.stop_words.contains(&word.to_lowercase())
+    }
+}
+
+///
+/// Lower-cased words of a text, splitting on Unicode word boundaries. Chinese text should be cut with a `Segmenter` instead.
+///
+pub fn split_words(text: &str) -> Vec<String> {
+    text.unicode_words().map(|w| w.to_lowercase()).collect()
+}
+
+///
+/// Keywords of a segmented document scored by term frequency times inverse document frequency
+///
+pub fn tf_idf<W: AsRef<str>>(words: &[W], idf: &IdfTable, config: &KeywordConfig) -> Vec<Keyword> {
+    let words: Vec<(&str, Option<&str>)> = words.iter().map(|w| (w.as_ref(), None)).collect();
+    tf_idf_with(&words, idf, config)
+}
+
+///
+/// `tf_idf` over (word, part-of-speech tag) pairs such as the output of `Segmenter::tag`, keeping the tags of `allowed_pos`
+///
+pub fn tf_idf_tagged<W: AsRef<str>, T: AsRef<str>>(words: &[(W, T)], idf: &IdfTable, config: &KeywordConfig) -> Vec<Keyword> {
+    let words: Vec<(&str, Option<&str>)> = words.iter().map(|(w, t)| (w.as_ref(), Some(t.as_ref()))).collect();
+    tf_idf_with(&words, idf, config)
+}
+
+///
+/// Keywords of a segmented document ranked by TextRank: PageRank over a graph linking words that occur within `window` words
+/// of each other. Scores are scaled so that the best keyword has 1.
+///
+pub fn text_rank<W: AsRef<str>>(words: &[W], config: &KeywordConfig) -> Vec<Keyword> {
+    let words: Vec<(&str, Option<&str>)> = words.iter().map(|w| (w.as_ref(), None)).collect();
+    text_rank_with(&words, config)
+}
+
+///
+/// `text_rank` over (word, part-of-speech tag) pairs such as the output of `Segmenter::tag`, keeping the tags of `allowed_pos`
+///
+pub fn text_rank_tagged<W: AsRef<str>, T: AsRef<str>>(words: &[(W, T)], config: &KeywordConfig) -> Vec<Keyword> {
+    let words: Vec<(&str, Option<&str>)> = words.iter().map(|(w, t)| (w.as_ref(), Some(t.as_ref()))).collect();
+    text_rank_with(&words, config)
+}
+
+fn tf_idf_with(words: &[(&str, Option<&str>)], idf: &IdfTable, config: &KeywordConfig) -> Vec<Keyword> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut total = 0;
+    for &(word, pos) in words {
+        if config.accepts(word, pos) {
+            *counts.entry(word).or_insert(0) += 1;
+            total += 1;
+        }
+    }
+    let keywords = counts
+        .into_iter()
+        .map(|(word, count)| Keyword { word: word.to_string(), score: count as f64 / total as f64 * idf.idf(word) })
+        .collect();
+    top_keywords(keywords, config.top_k)
+}
+
+fn text_rank_with(words: &[(&str, Option<&str>)], config: &KeywordConfig) -> Vec<Keyword> {
+    let mut ids: HashMap<&str, usize> = HashMap::new();
+    let mut vocabulary: Vec<&str> = Vec::new();
+    // node of each word, None when it is filtered out but still counts in the window
+    let nodes: Vec<Option<usize>> = words
+        .iter()
+        .map(|&(word, pos)| {
+            if !config.accepts(word, pos) {
+                return None;
+            }
+            Some(*ids.entry(word).or_insert_with(|| {
+                vocabulary.push(word);
+                vocabulary.len() - 1
+            }))
+        })
+        .collect();
+    let mut edges: Vec<HashMap<usize, f64>> = vec![HashMap::new(); vocabulary.len()];
+    for (i, a) in nodes.iter().enumerate() {
+        let a = match a {
+            Some(a) => *a,
+            None => continue,
+        };
+        for b in nodes.iter().skip(i + 1).take(config.window.saturating_sub(1)).flatten() {
+            if a != *b {
+                *edges[a].entry(*b).or_insert(0.0) += 1.0;
+                *edges[*b].entry(a).or_insert(0.0) += 1.0;
+            }
+        }
+    }
+    let scores = page_rank(&edges);
+    let max = scores.iter().copied().fold(0.0, f64::max);
+    let keywords = vocabulary
+        .iter()
+        .zip(scores)
+        .map(|(word, score)| Keyword { word: word.to_string(), score: if max > 0.0 { score / max } else { score } })
+        .collect();
+    top_keywords(keywords, config.top_k)
+}
+
+///
+/// Weighted PageRank on an undirected graph given as the neighbours of each node
+///
+fn page_rank(edges: &[HashMap<usize, f64>]) -> Vec<f64> {
+    let damping_factor = 0.85;
+    let n = edges.len();
+    let out_weights: Vec<f64> = edges.iter().map(|e| e.values().sum()).collect();
+    let mut scores = vec![1.0 / n as f64; n];
+    for _ in 0..100 {
+        let mut next = vec![(1.0 - damping_factor) / n as f64; n];
+        for (node, neighbours) in edges.iter().enumerate() {
+            for (&other, &weight) in neighbours {
+                next[node] += damping_factor * weight / out_weights[other] * scores[other];
+            }
+        }
+        let delta: f64 = next.iter().zip(&scores).map(|(a, b)| (a - b).abs()).sum();
+        scores = next;
+        if delta < 1e-6 {
+            break;
+        }
+    }
+    scores
+}
+
+///
+/// Best keywords first, ties broken by word so that the order is stable
+///
+fn top_keywords(mut keywords: Vec<Keyword>, top_k: usize) -> Vec<Keyword> {
+    keywords.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.word.cmp(&b.word)));
+    if top_k > 0 {
+        keywords.truncate(top_k);
+    }
+    keywords
+}
@@ -2,5 +2,10 @@ pub mod summarizer;
 pub mod word2vec;
 pub mod token;
 pub mod segmentation;
+pub mod dictionary;
+pub mod hmm;
+pub mod trainer;
+pub mod evaluation;
+pub mod keywords;
 mod chardata;
 mod toksiter;
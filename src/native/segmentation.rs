@@ -1,147 +1,604 @@
 
 
-use std::*;
 extern crate  unicode_segmentation;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use log::warn;
+use crate::error::Error;
 use unicode_segmentation::UnicodeSegmentation;
+use crate::native::dictionary::{parse_entry, Dictionary};
+use crate::native::hmm::Hmm;
+use crate::native::trainer::SegmentationModel;
 
 
 
+///
+/// Maximum matching methods available to the segmenter
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SegmentMethod {
+    /// Forward Maximum Matching
+    Fmm,
+    /// Backward Maximum Matching
+    Bmm,
+    /// Bidirectional Maximum Matching
+    #[default]
+    Bimm,
+    /// Maximum probability route through the DAG of dictionary words, using word frequencies
+    Dag,
+}
+
+impl FromStr for SegmentMethod {
+    type Err = Error;
+
+    ///
+    /// Parse the method names used by `get_segmentation`: "fmm", "bmm", "bimm", "dag" or "" for the default
+    ///
+    fn from_str(s: &str) -> Result<SegmentMethod, Error> {
+        match s {
+            "fmm" => Ok(SegmentMethod::Fmm),
+            "bmm" => Ok(SegmentMethod::Bmm),
+            "bimm" | "" => Ok(SegmentMethod::Bimm),
+            "dag" => Ok(SegmentMethod::Dag),
+            _ => Err(Error::Parse(format!("unknown segmentation method {:?}", s))),
+        }
+    }
+}
+
+///
+/// Settings of `get_segmentation_with_config`
+///
+/// - `dict_path`: the dictionary file path where each line is a term, empty to split graphemes only,
+/// - `stopwords_path`: the stopwords file path where each line is a stop word, may be empty,
+/// - `method`: the maximum matching method,
+/// - `max_word_len`: the longest word tried by the matchers, in atomic units (see `get_atomic_units`): "Rust2024", a number
+///   or a URL counts as one unit, like a single Han character. `None` uses the length in graphemes of the longest dictionary entry,
+/// - `hmm_path`: a BMES model file (see `Hmm`) used to join runs of single graphemes into unknown words, empty to skip this stage,
+/// - `model_path`: a model written by the `trainer`, providing both the frequency dictionary and the HMM;
+///   words of `dict_path` are added to it and `hmm_path` replaces its HMM,
+/// - `pos_hmm_path`: a part-of-speech model file (see `Hmm`) tagging words without a dictionary tag, empty to use the one of the model if any.
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SegmenterConfig {
+    pub dict_path: String,
+    pub stopwords_path: String,
+    pub method: SegmentMethod,
+    pub max_word_len: Option<usize>,
+    pub hmm_path: String,
+    pub model_path: String,
+    pub pos_hmm_path: String,
+}
+
 ///
 /// Get word segmentation results from customized dictionaries and methods
 ///
 /// Parameters:
 ///
-///     _sentence: a string,
+/// - `_sentence`: a string,
+/// - `dict_path`: the dictionary file path where each line is a term,
+/// - `stopwords_path`: the stopwords file path  where each line is a stop word,
+/// - `method`: if empty, use 'bimm', other optional values: fmm, bmm, dag.
 ///
-///     dict_path: the dictionary file path where each line is a term,
+pub fn get_segmentation(_sentence:&str,dict_path:&str,stopwords_path:&str,method:&str)->Vec<String>{
+    let method=match method.parse(){
+        Ok(method)=>method,
+        Err(e)=>{
+            warn!("{}",e);
+            return Vec::new();
+        }
+    };
+    let config=SegmenterConfig{
+        dict_path:dict_path.to_string(),
+        stopwords_path:stopwords_path.to_string(),
+        method,
+        ..Default::default()
+    };
+    get_segmentation_with_config(_sentence,&config)
+}
+
 ///
-///     stopwords_path: the stopwords file path  where each line is a stop word,
+/// Same as `get_segmentation`, with the method and maximum word length given by a `SegmenterConfig`
 ///
-///     method: if empty, use 'bimm', other optional values: fmm, bmm.
+pub fn get_segmentation_with_config(_sentence:&str,config:&SegmenterConfig)->Vec<String>{
+    if config.dict_path.is_empty() && config.model_path.is_empty(){
+        return get_atomic_units(_sentence).into_iter().map(String::from).collect();
+    }
+    match Segmenter::load(config,false){
+        Ok(segmenter)=>segmenter.cut(_sentence),
+        Err(_)=>get_atomic_units(_sentence).into_iter().map(String::from).collect(),
+    }
+}
+
 ///
-pub fn get_segmentation(_sentence:&str,dict_path:&str,stopwords_path:&str,method:&str)->Vec<String>{
+/// Same as `get_segmentation`, but returns an IO error when the dictionary or stopwords file cannot be read
+/// and a parse error for an unknown method
+///
+pub fn try_get_segmentation(_sentence:&str,dict_path:&str,stopwords_path:&str,method:&str)->crate::error::Result<Vec<String>>{
+    let config=SegmenterConfig{
+        dict_path:dict_path.to_string(),
+        stopwords_path:stopwords_path.to_string(),
+        method:method.parse()?,
+        ..Default::default()
+    };
+    try_get_segmentation_with_config(_sentence,&config)
+}
+
+///
+/// Same as `get_segmentation_with_config`, but returns an IO error when the dictionary or stopwords file cannot be read
+///
+pub fn try_get_segmentation_with_config(_sentence:&str,config:&SegmenterConfig)->crate::error::Result<Vec<String>>{
+    if config.dict_path.is_empty() && config.model_path.is_empty(){
+        return Ok(get_atomic_units(_sentence).into_iter().map(String::from).collect());
+    }
+    Ok(Segmenter::from_config(config)?.cut(_sentence))
+}
 
-    if dict_path.eq(""){
-        let result= get_word_list(_sentence);
-        let mut result_final:Vec<String>=Vec::new();
-        for r in result{
-            result_final.push(String::from(r));
+/// Priority of the words of the base dictionary or model
+pub const BASE_PRIORITY:i32=0;
+
+///
+/// A segmenter keeping its dictionary, stop words and HMM in memory so they are loaded once and reused across calls.
+/// A base dictionary can be extended with user dictionaries and edited at runtime.
+///
+/// When several dictionaries define the same word, the frequency and tag of the highest priority one are kept.
+/// The base dictionary has `BASE_PRIORITY` and words added with `add_word` always win.
+///
+#[derive(Debug, Clone, Default)]
+pub struct Segmenter{
+    dict:Dictionary,
+    stop_words:HashSet<String>,
+    hmm:Option<Hmm>,
+    pos_hmm:Option<Hmm>,
+    config:SegmenterConfig,
+    priorities:HashMap<String,i32>,
+}
+
+impl Segmenter{
+    ///
+    /// A segmenter over a base dictionary, using 'bimm' without stop words nor HMM
+    ///
+    pub fn new(dict:Dictionary)->Segmenter{
+        Segmenter{dict,..Default::default()}
+    }
+
+    ///
+    /// Load the dictionary, model, HMM and stop words named by a config
+    ///
+    pub fn from_config(config:&SegmenterConfig)->crate::error::Result<Segmenter>{
+        Segmenter::load(config,true)
+    }
+
+    ///
+    /// When not `strict`, a file that cannot be loaded is logged and skipped
+    ///
+    fn load(config:&SegmenterConfig,strict:bool)->crate::error::Result<Segmenter>{
+        let mut segmenter=Segmenter{config:config.clone(),..Default::default()};
+        if !config.model_path.is_empty(){
+            if let Some(model)=loaded(&config.model_path,strict,SegmentationModel::from_file(&config.model_path))?{
+                segmenter.dict=model.dictionary;
+                segmenter.hmm=Some(model.hmm);
+                segmenter.pos_hmm=model.pos_hmm;
+            }
+        }
+        if !config.dict_path.is_empty(){
+            if let Some(words)=loaded(&config.dict_path,strict,Dictionary::from_file(&config.dict_path).map_err(Error::from))?{
+                if segmenter.dict.is_empty(){
+                    segmenter.dict=words;
+                }else{
+                    segmenter.add_dictionary(&words,BASE_PRIORITY);
+                }
+            }
+        }
+        if !config.hmm_path.is_empty(){
+            if let Some(hmm)=loaded(&config.hmm_path,strict,Hmm::from_file(&config.hmm_path))?{
+                segmenter.hmm=Some(hmm);
+            }
         }
-        return result_final;
+        if !config.pos_hmm_path.is_empty(){
+            if let Some(hmm)=loaded(&config.pos_hmm_path,strict,Hmm::from_file_with_states(&config.pos_hmm_path,&[]))?{
+                segmenter.pos_hmm=Some(hmm);
+            }
+        }
+        if !config.stopwords_path.is_empty(){
+            if let Some(words)=loaded(&config.stopwords_path,strict,try_load_dictionary(&config.stopwords_path).map_err(Error::from))?{
+                segmenter.stop_words=words.into_iter().filter(|w| !w.is_empty()).collect();
+            }
+        }
+        Ok(segmenter)
     }
 
-    // println!("loading common dictionary");
-    let common_words=load_dictionary(dict_path);
+    pub fn method(mut self,method:SegmentMethod)->Segmenter{
+        self.config.method=method;
+        self
+    }
 
+    ///
+    /// Longest word in atomic units tried by the matchers, `None` for the longest dictionary entry
+    ///
+    pub fn max_word_len(mut self,max_word_len:Option<usize>)->Segmenter{
+        self.config.max_word_len=max_word_len;
+        self
+    }
 
-    let stop_words:Vec<String>;
-    if stopwords_path.eq(""){
-        // println!("loading stopwords dictionary");
-        stop_words=Vec::new();
-    }else{
-        stop_words=load_dictionary(stopwords_path);
+    ///
+    /// BMES model joining runs of single graphemes after the dictionary stage
+    ///
+    pub fn hmm(mut self,hmm:Hmm)->Segmenter{
+        self.hmm=Some(hmm);
+        self
     }
 
+    ///
+    /// Part-of-speech model with tags as states, used by `tag` for words without a dictionary tag
+    ///
+    pub fn pos_hmm(mut self,pos_hmm:Hmm)->Segmenter{
+        self.pos_hmm=Some(pos_hmm);
+        self
+    }
 
-    // println!("doing segmentation tasks");
+    pub fn stop_words<I,S>(mut self,words:I)->Segmenter where I:IntoIterator<Item=S>,S:Into<String>{
+        self.stop_words.extend(words.into_iter().map(Into::into));
+        self
+    }
 
-    let sentence=_sentence.graphemes(true).collect::<Vec<&str>>();
-    let mut list_result:Vec<String>=Vec::new();
+    pub fn dictionary(&self)->&Dictionary{
+        &self.dict
+    }
 
-    if method=="bimm" || method==""{
-        list_result=bimm(sentence,common_words);
-    }else  if method=="fmm"{
-        list_result=fmm(sentence,common_words);
-    }else if method=="bmm"{
-        list_result=bmm(sentence,common_words);
+    ///
+    /// Segment a sentence, removing stop words
+    ///
+    pub fn cut(&self,_sentence:&str)->Vec<String>{
+        segment_with(_sentence,&self.dict,&self.stop_words,&self.config,self.hmm.as_ref())
     }
 
+    ///
+    /// Segment a sentence and return the position of each word, removing stop words
+    ///
+    pub fn cut_spans(&self,_sentence:&str)->Vec<WordSpan>{
+        word_spans(segment_words(_sentence,&self.dict,&self.config,self.hmm.as_ref()),&self.dict,&self.stop_words)
+    }
+
+    ///
+    /// Segment a sentence and tag each word with its part of speech, removing stop words.
+    /// See `tag_words` for where the tags come from.
+    ///
+    pub fn tag(&self,_sentence:&str)->Vec<(String,String)>{
+        let words=segment_words(_sentence,&self.dict,&self.config,self.hmm.as_ref());
+        let mut tagged=tag_words(&words,&self.dict,self.pos_hmm.as_ref());
+        if !self.stop_words.is_empty(){
+            tagged.retain(|(word,_)| !self.stop_words.contains(word));
+        }
+        tagged
+    }
 
-    if !stopwords_path.eq(""){
-        let mut meaningful_words:Vec<String>=Vec::new();
-        // println!("removing stop words...");
-        for word in list_result{
-            if !stop_words.contains(&word){
-                meaningful_words.push(word);
+    ///
+    /// Load a user dictionary file ("word [freq [pos]]" per line) with a priority, returns the number of entries applied.
+    /// Words without frequency get the one from `suggest_freq`, so that they are kept whole.
+    ///
+    pub fn load_user_dictionary<P:AsRef<Path>>(&mut self,path:P,priority:i32)->crate::error::Result<usize>{
+        let mut applied=0;
+        for line in _read_lines(path)?{
+            if let Some((word,freq,pos)) = parse_entry(&line?){
+                let freq=freq.unwrap_or_else(|| self.suggest_freq(&[&word],false));
+                applied+=self.apply(&word,freq,pos.as_deref(),priority) as usize;
             }
         }
-        return meaningful_words;
+        Ok(applied)
+    }
+
+    ///
+    /// Merge the entries of a dictionary with a priority, returns the number of entries applied
+    ///
+    pub fn add_dictionary(&mut self,dict:&Dictionary,priority:i32)->usize{
+        dict.entries().into_iter().filter(|(word,freq,pos)| self.apply(word,*freq,pos.as_deref(),priority)).count()
+    }
+
+    ///
+    /// Add a word or change its frequency and tag. Without frequency, `suggest_freq` makes sure it is kept whole.
+    ///
+    pub fn add_word(&mut self,word:&str,freq:Option<u64>,pos:Option<&str>){
+        let freq=freq.unwrap_or_else(|| self.suggest_freq(&[word],false));
+        self.apply(word,freq,pos,i32::MAX);
+    }
+
+    ///
+    /// Remove a word, returns false when it was not in the dictionary
+    ///
+    pub fn del_word(&mut self,word:&str)->bool{
+        self.priorities.remove(word);
+        self.dict.remove(word)
+    }
+
+    ///
+    /// Frequency making the 'dag' method keep `segments` joined (one segment) or split (several segments),
+    /// computed from the frequencies of its parts as in jieba. With `tune`, the word gets this frequency.
+    ///
+    pub fn suggest_freq(&mut self,segments:&[&str],tune:bool)->u64{
+        let total=self.dict.total_freq().max(1) as f64;
+        let word=segments.concat();
+        let freq_of=|dict:&Dictionary,w:&str,default:u64| dict.freq(w).unwrap_or(default) as f64;
+        let freq=if segments.len()==1{
+            let units=get_atomic_units(&word);
+            let p:f64=dag(&units,&self.dict).iter().map(|seg| freq_of(&self.dict,seg,1)/total).product();
+            ((p*total) as u64+1).max(self.dict.freq(&word).unwrap_or(1))
+        }else{
+            let p:f64=segments.iter().map(|seg| freq_of(&self.dict,seg,1)/total).product();
+            ((p*total) as u64).min(self.dict.freq(&word).unwrap_or(0))
+        };
+        if tune{
+            self.add_word(&word,Some(freq),None);
+        }
+        freq
+    }
+
+    fn apply(&mut self,word:&str,freq:u64,pos:Option<&str>,priority:i32)->bool{
+        let current=match self.priorities.get(word){
+            Some(&p)=>p,
+            None if self.dict.contains(word)=>BASE_PRIORITY,
+            None=>i32::MIN,
+        };
+        if priority<current{
+            return false;
+        }
+        let pos=pos.map(String::from).or_else(|| self.dict.pos(word).map(String::from));
+        self.dict.insert_entry(word,freq,pos.as_deref());
+        self.priorities.insert(word.to_string(),priority);
+        true
+    }
+}
+
+fn loaded<T>(path:&str,strict:bool,result:crate::error::Result<T>)->crate::error::Result<Option<T>>{
+    match result{
+        Ok(value)=>Ok(Some(value)),
+        Err(e) if !strict=>{
+            warn!("failed to load {}: {}",path,e);
+            Ok(None)
+        }
+        Err(e)=>Err(e),
+    }
+}
+
+///
+/// Segment a sentence with a dictionary loaded once and shared across calls
+///
+/// - `method`: if empty, use 'bimm', other optional values: fmm, bmm, dag.
+///
+pub fn segment(_sentence:&str,dict:&Dictionary,method:&str)->Vec<String>{
+    match method.parse(){
+        Ok(method)=>segment_with_config(_sentence,dict,&SegmenterConfig{method,..Default::default()}),
+        Err(e)=>{
+            warn!("{}",e);
+            Vec::new()
+        }
+    }
+}
+
+///
+/// Segment a sentence with a shared dictionary, using the method and maximum word length of `config`.
+/// The paths of `config` are not used, pass the result to `Hmm::merge_single_graphemes` to join unknown words.
+///
+pub fn segment_with_config(_sentence:&str,dict:&Dictionary,config:&SegmenterConfig)->Vec<String>{
+    segment_with(_sentence,dict,&HashSet::new(),config,None)
+}
+
+fn segment_with(_sentence:&str,dict:&Dictionary,stop_words:&HashSet<String>,config:&SegmenterConfig,hmm:Option<&Hmm>)->Vec<String>{
+    let list_result=segment_words(_sentence,dict,config,hmm);
+    if stop_words.is_empty(){
+        list_result
     }else{
-        return list_result;
+        list_result.into_iter().filter(|word| !stop_words.contains(word)).collect()
     }
+}
 
+///
+/// Segment without removing stop words, so the words concatenate back to the sentence
+///
+fn segment_words(_sentence:&str,dict:&Dictionary,config:&SegmenterConfig,hmm:Option<&Hmm>)->Vec<String>{
+    let sentence=get_atomic_units(_sentence);
+    let max_len=config.max_word_len.unwrap_or_else(|| dict.max_word_len());
+
+    let list_result=match config.method{
+        SegmentMethod::Fmm=>fmm_with_max_len(&sentence,dict,max_len),
+        SegmentMethod::Bmm=>bmm_with_max_len(&sentence,dict,max_len),
+        SegmentMethod::Bimm=>bimm_with_max_len(&sentence,dict,max_len),
+        SegmentMethod::Dag=>dag_with_max_len(&sentence,dict,max_len),
+    };
+    match hmm{
+        Some(hmm)=>hmm.merge_single_graphemes(list_result),
+        None=>list_result,
+    }
+}
 
+///
+/// A segmented word with its position in the sentence
+///
+/// - `byte_start`, `byte_end`: byte offsets, so that `&sentence[byte_start..byte_end]` is the word,
+/// - `char_start`, `char_end`: character offsets, as the `start_char` and `end_char` of Stanza annotations,
+/// - `in_dict`: whether the word is a dictionary entry, false for out-of-vocabulary fallbacks such as single graphemes or HMM words.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WordSpan{
+    pub text:String,
+    pub byte_start:usize,
+    pub byte_end:usize,
+    pub char_start:usize,
+    pub char_end:usize,
+    pub in_dict:bool,
+}
+
+///
+/// Segment a sentence with a shared dictionary and return the position of each word
+///
+/// - `method`: if empty, use 'bimm', other optional values: fmm, bmm, dag.
+///
+pub fn segment_spans(_sentence:&str,dict:&Dictionary,method:&str)->Vec<WordSpan>{
+    match method.parse(){
+        Ok(method)=>word_spans(segment_words(_sentence,dict,&SegmenterConfig{method,..Default::default()},None),dict,&HashSet::new()),
+        Err(e)=>{
+            warn!("{}",e);
+            Vec::new()
+        }
+    }
+}
+
+///
+/// Segment a sentence with a shared dictionary and tag each word with its part of speech
+///
+/// - `method`: if empty, use 'bimm', other optional values: fmm, bmm, dag.
+///
+pub fn segment_pos(_sentence:&str,dict:&Dictionary,method:&str)->Vec<(String,String)>{
+    match method.parse(){
+        Ok(method)=>tag_words(&segment_words(_sentence,dict,&SegmenterConfig{method,..Default::default()},None),dict,None),
+        Err(e)=>{
+            warn!("{}",e);
+            Vec::new()
+        }
+    }
+}
+
+///
+/// Tag segmented words as (word, tag) pairs. A word gets, in order:
+///
+/// - its tag in the dictionary,
+/// - "m" for numbers, "eng" for Latin words, URLs and emails, "x" for whitespace and punctuation,
+/// - the tag decoded by `pos_hmm` from the tags of its neighbours, or "x" without a model.
+///
+pub fn tag_words<S:AsRef<str>>(words:&[S],dict:&Dictionary,pos_hmm:Option<&Hmm>)->Vec<(String,String)>{
+    let words:Vec<&str>=words.iter().map(|w| w.as_ref()).collect();
+    let known:Vec<Option<&str>>=words.iter().map(|w| dict.pos(w).or_else(|| unit_tag(w))).collect();
+    let tags=match pos_hmm{
+        Some(hmm) if known.iter().any(|k| k.is_none())=>hmm.viterbi_with_known(&words,&known),
+        _=>known.iter().map(|k| k.unwrap_or("x").to_string()).collect(),
+    };
+    words.into_iter().map(String::from).zip(tags).collect()
+}
+
+///
+/// Tag of words recognised by their shape, as `get_atomic_units` does
+///
+fn unit_tag(word:&str)->Option<&'static str>{
+    let whole=|len:Option<usize>| len==Some(word.len());
+    if whole(number_len(word)){
+        Some("m")
+    }else if whole(url_len(word)) || whole(email_len(word)) || whole(letters_len(word)){
+        Some("eng")
+    }else if !word.is_empty() && word.chars().all(|c| !c.is_alphanumeric()){
+        Some("x")
+    }else{
+        None
+    }
+}
+
+///
+/// Offsets of consecutive words, leaving out stop words
+///
+fn word_spans(words:Vec<String>,dict:&Dictionary,stop_words:&HashSet<String>)->Vec<WordSpan>{
+    let mut spans=Vec::with_capacity(words.len());
+    let (mut byte_start,mut char_start)=(0,0);
+    for word in words{
+        let byte_end=byte_start+word.len();
+        let char_end=char_start+word.chars().count();
+        if !stop_words.contains(&word){
+            let in_dict=dict.contains(&word);
+            spans.push(WordSpan{text:word,byte_start,byte_end,char_start,char_end,in_dict});
+        }
+        byte_start=byte_end;
+        char_start=char_end;
+    }
+    spans
 }
 
 ///
 /// Bidirection Maximum Matching Method
 ///
-pub fn bimm(sentence:Vec<&str>, words_dict:Vec<String>)->Vec<String>{
-    let s1=sentence.clone();
-    let s2=sentence.clone();
-    let dict1=words_dict.clone();
-    let dict2=words_dict.clone();
-    let forward =fmm(s1,dict1);
-    let backward=bmm(s2,dict2);
-    // println!("FMM: {:?}",forward);
-    // println!("BMM: {:?}",backward);
-    let mut f_single_word=0;
-    let mut b_single_word=0;
-    let mut tot_fmm=forward.len();
-    let mut tot_bmm=backward.len();
-    let mut oov_fmm=0;
-    let mut oov_bmm=0;
-    let mut score_fmm=0;
-    let mut score_bmm=0;
+pub fn bimm(sentence:&[&str], dict:&Dictionary)->Vec<String>{
+    bimm_with_max_len(sentence,dict,dict.max_word_len())
+}
+
+///
+/// Bidirection Maximum Matching Method trying words of at most `max_len` graphemes
+///
+pub fn bimm_with_max_len(sentence:&[&str], dict:&Dictionary, max_len:usize)->Vec<String>{
+    let forward =fmm_with_max_len(sentence,dict,max_len);
+    let backward=bmm_with_max_len(sentence,dict,max_len);
     if forward==backward{
         return backward;
+    }
+    let single_words=|words:&[String]| words.iter().filter(|w| w.graphemes(true).count()==1).count();
+    let oov_words=|words:&[String]| words.iter().filter(|w| !dict.contains(w)).count();
+
+    let mut score_fmm=0;
+    let mut score_bmm=0;
+    let (oov_fmm,oov_bmm)=(oov_words(&forward),oov_words(&backward));
+    if oov_fmm>oov_bmm{
+        score_bmm+=1;
+    }else if oov_fmm<oov_bmm{
+        score_fmm+=1;
+    }
+    if forward.len()>backward.len(){
+        score_bmm+=1;
+    }else if forward.len()<backward.len(){
+        score_fmm+=1;
+    }
+    let (f_single_word,b_single_word)=(single_words(&forward),single_words(&backward));
+    if f_single_word>b_single_word{
+        score_bmm+=1;
+    }else if f_single_word<b_single_word{
+        score_fmm+=1;
+    }
+
+    if score_fmm>score_bmm{
+        forward
     }else{
-        for each in forward.clone(){
-            if each.len()==1{
-                f_single_word+=1;
-            }
-        }
-        for each in backward.clone(){
-            if each.len()==1{
-                b_single_word+=1;
-            }
-        }
-        for each in forward.clone(){
-            if !words_dict.contains(&each){
-                oov_fmm+=1;
-            }
-        }
-        for each in backward.clone(){
-            if !words_dict.contains(&each){
-                oov_bmm+=1;
-            }
-        }
-        if oov_fmm>oov_bmm{
-            score_bmm+=1;
-        }
-        if oov_fmm<oov_bmm{
-            score_fmm+=1;
-        }
-        if tot_fmm>tot_bmm{
-            score_bmm+=1;
-        }else if tot_fmm<tot_bmm{
-            score_fmm+=1;
-        }
+        backward
+    }
+}
 
-        if f_single_word>b_single_word{
-            score_bmm+=1;
-        }else if f_single_word<b_single_word{
-            score_fmm+=1;
+///
+/// Directed acyclic graph of a sentence: for each grapheme position, the length and frequency of the dictionary words
+/// starting there (at most `max_len` graphemes), shortest first. A single grapheme is always a candidate.
+///
+pub fn get_dag(sentence:&[&str],dict:&Dictionary,max_len:usize)->Vec<Vec<(usize,u64)>>{
+    (0..sentence.len()).map(|i|{
+        let end=(i+max_len).min(sentence.len());
+        let mut edges=dict.prefix_freqs(&sentence[i..end]);
+        if edges.first().map(|&(len,_)| len!=1).unwrap_or(true){
+            edges.insert(0,(1,0));
         }
+        edges
+    }).collect()
+}
 
-        if score_fmm<score_bmm{
-            return forward;
-        }else{
-            return backward;
-        }
+///
+/// Word Segmentation Based on the maximum probability route through the DAG of dictionary words.
+/// Each word is scored by its dictionary frequency (load a "word freq [pos]" dictionary), unknown graphemes count as 1.
+///
+pub fn dag(sentence:&[&str],dict:&Dictionary)->Vec<String>{
+    dag_with_max_len(sentence,dict,dict.max_word_len())
+}
 
+///
+/// Maximum probability route segmentation trying words of at most `max_len` graphemes
+///
+pub fn dag_with_max_len(sentence:&[&str],dict:&Dictionary,max_len:usize)->Vec<String>{
+    let n=sentence.len();
+    let log_total=(dict.total_freq().max(1) as f64).ln();
+    // route[i]: best log probability of sentence[i..] and the length of its first word
+    let mut route:Vec<(f64,usize)>=vec![(0.0,0);n+1];
+    for (i,edges) in get_dag(sentence,dict,max_len).into_iter().enumerate().rev(){
+        route[i]=edges.into_iter()
+            .map(|(len,freq)| ((freq.max(1) as f64).ln()-log_total+route[i+len].0,len))
+            .fold((f64::NEG_INFINITY,1),|best,candidate| if candidate.0>=best.0 {candidate} else {best});
+    }
+    let mut list_words:Vec<String>=Vec::new();
+    let mut index=0;
+    while index<n{
+        let len=route[index].1;
+        list_words.push(sentence[index..index+len].concat());
+        index+=len;
     }
+    list_words
 }
 
 ///
@@ -149,111 +606,169 @@ pub fn bimm(sentence:Vec<&str>, words_dict:Vec<String>)->Vec<String>{
 /// Get meaningful word list from non-english text like Chinese with the use of unicode-segmentation. (Not char list)
 ///
 pub fn get_word_list(str:&str)->Vec<&str>{
-    // let s = "我喜欢吃苹果，也爱打羽毛球";
-    let g = str.graphemes(true).collect::<Vec<&str>>();
-    // println!("{:?}",g);
-    return g;
+    str.graphemes(true).collect::<Vec<&str>>()
 }
 
 ///
-/// Word Segmentation Based on Backward Maximum Matching
+/// Split a text into the units matched by the segmenters: URLs, emails, numbers (with decimals and percentages),
+/// runs of Latin, Greek or Cyrillic letters and runs of whitespace are kept whole, anything else is split into graphemes.
+/// For "我用Rust 1.56写NLP" this gives "我", "用", "Rust", " ", "1.56", "写", "NLP".
 ///
-pub fn bmm(sentence:Vec<&str>,dict:Vec<String>)->Vec<String>{
+pub fn get_atomic_units(text:&str)->Vec<&str>{
+    let mut units=Vec::new();
+    let mut rest=text;
+    while let Some(first)=rest.graphemes(true).next(){
+        let len=url_len(rest)
+            .or_else(|| email_len(rest))
+            .or_else(|| number_len(rest))
+            .or_else(|| letters_len(rest))
+            .or_else(|| whitespace_len(rest))
+            .unwrap_or(first.len());
+        units.push(&rest[..len]);
+        rest=&rest[len..];
+    }
+    units
+}
 
-    let mut list_words:Vec<String>=Vec::new();
-    let mut index:i32=sentence.len() as i32;
-    let window_size:i32=4;
-    while index>0{
-        let mut match_flag=false;
-        let mut i=window_size.clone();
-        // println!("i={i}");
-        while i>=0{
-            // println!("i={}",i);
-            let a;
-            if index-i<0{
-                a=0 as usize;
-            }else{
-                a=(index-i) as usize;
-            }
-            // let a = (index-i) as usize;
-            let b =index as usize;
-            // println!("({},{})",a,b);
-            let sub_str=sentence[a..b].concat();
-            if dict.contains(&sub_str) {
-                match_flag = true;
-                list_words.push(sub_str);
-                index -= i;
-                break;
+fn is_letter(c:char)->bool{
+    // Latin, IPA, Greek and Cyrillic letters, and combining diacritical marks
+    (c.is_alphabetic() && (c as u32)<0x0530) || ('\u{0300}'..='\u{036F}').contains(&c)
+}
+
+fn is_digit(c:char)->bool{
+    c.is_ascii_digit() || ('０'..='９').contains(&c)
+}
+
+fn url_len(s:&str)->Option<usize>{
+    let starts_with=|prefix:&str| s.as_bytes().get(..prefix.len()).map(|p| p.eq_ignore_ascii_case(prefix.as_bytes())).unwrap_or(false);
+    if !(starts_with("http://") || starts_with("https://") || starts_with("www.")){
+        return None;
+    }
+    let end=s.find(|c:char| c.is_whitespace() || !c.is_ascii()).unwrap_or(s.len());
+    let url=s[..end].trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '\'', '"']);
+    Some(url.len())
+}
+
+// longest local part and domain of an email address
+const MAX_EMAIL_LOCAL:usize=64;
+const MAX_EMAIL_DOMAIN:usize=255;
+
+fn email_len(s:&str)->Option<usize>{
+    let local=run_len(s,MAX_EMAIL_LOCAL,|c| c.is_ascii_alphanumeric() || "._%+-".contains(c));
+    if local==0 || !s[local..].starts_with('@'){
+        return None;
+    }
+    let domain_start=local+1;
+    let domain_len=run_len(&s[domain_start..],MAX_EMAIL_DOMAIN,|c| c.is_ascii_alphanumeric() || c=='.' || c=='-');
+    let domain=s[domain_start..domain_start+domain_len].trim_end_matches('.');
+    if domain.starts_with('.') || !domain.contains('.'){
+        return None;
+    }
+    Some(domain_start+domain.len())
+}
+
+///
+/// Byte length of the leading characters matching `f`, looking at no more than `max` characters
+///
+fn run_len<F:Fn(char)->bool>(s:&str,max:usize,f:F)->usize{
+    s.chars().take(max).take_while(|&c| f(c)).map(char::len_utf8).sum()
+}
+
+///
+/// Character starting at a byte offset
+///
+fn char_at(s:&str,pos:usize)->Option<char>{
+    s.get(pos..).and_then(|rest| rest.chars().next())
+}
+
+fn number_len(s:&str)->Option<usize>{
+    let mut end=0;
+    while let Some(c)=char_at(s,end).filter(|&c| is_digit(c)){
+        end+=c.len_utf8();
+        // decimal point or thousands separator between digits
+        if let Some(sep)=char_at(s,end).filter(|&c| c=='.' || c==','){
+            if char_at(s,end+sep.len_utf8()).map(is_digit).unwrap_or(false){
+                end+=sep.len_utf8();
             }
-            i-=1;
         }
-        if match_flag==false{
+    }
+    if end==0{
+        return None;
+    }
+    if let Some(percent)=char_at(s,end).filter(|&c| c=='%' || c=='％'){
+        end+=percent.len_utf8();
+    }
+    Some(end)
+}
 
-            if index-1<0{
-                index=1;
-            }
-            let a=(index-1) as usize;
-            list_words.push(String::from(sentence[a]));
-            index-=1;
+fn letters_len(s:&str)->Option<usize>{
+    let first=char_at(s,0).filter(|&c| is_letter(c))?;
+    let mut end=first.len_utf8();
+    while let Some(c)=char_at(s,end){
+        let joins_next=(c=='-' || c=='\'' || c=='’') && char_at(s,end+c.len_utf8()).map(|n| is_letter(n) || n.is_ascii_digit()).unwrap_or(false);
+        if is_letter(c) || c.is_ascii_digit() || c=='_' || joins_next{
+            end+=c.len_utf8();
+        }else{
+            break;
         }
     }
-    list_words.reverse();
-    return list_words;
+    Some(end)
+}
 
+fn whitespace_len(s:&str)->Option<usize>{
+    let len=s.find(|c:char| !c.is_whitespace()).unwrap_or(s.len());
+    if len==0{
+        None
+    }else{
+        Some(len)
+    }
 }
 
 ///
-/// Word Segmentation Based on Forward Maximum Matching
+/// Word Segmentation Based on Backward Maximum Matching
 ///
-pub fn fmm(sentence:Vec<&str>,dict:Vec<String>)->Vec<String>{
-    let token_len=sentence.len() as i32;
-    // println!("token len: {}",token_len);
-    let mut index:i32=0;
+pub fn bmm(sentence:&[&str],dict:&Dictionary)->Vec<String>{
+    bmm_with_max_len(sentence,dict,dict.max_word_len())
+}
 
+///
+/// Backward Maximum Matching trying words of at most `max_len` graphemes
+///
+pub fn bmm_with_max_len(sentence:&[&str],dict:&Dictionary,max_len:usize)->Vec<String>{
     let mut list_words:Vec<String>=Vec::new();
-    let window_size=4;
-    /*
-    for char in sentence.chars(){
-        println!("{}",char);
-    }
-     */
-    while index<token_len{
-        let mut match_flag=false;
-        let mut i=window_size.clone();
-        while i>=0{
-            //println!("i={}",i);
-
-            let a=index as usize;
-            let mut b=(index+i) as usize;
-           // println!("({},{})",a,b);
-            if b>(token_len) as usize{
-                b=token_len as usize;
-            }
-            let sub_str=sentence[a..b].concat();
-            //println!("sub_str: {}",sub_str);
-            if dict.contains(&sub_str){
-                match_flag=true;
-                list_words.push(sub_str);
-                index+=i;
-                break;
-            }
-            //println!();
-            i-=1;
-        }
-        if match_flag==false{
-            let a=index as usize;
-            let v=String::from(sentence[a]);
-            list_words.push(v);
-            index+=1;
-        }
+    let mut index=sentence.len();
+    while index>0{
+        let len=dict.longest_suffix(&sentence[..index],max_len).unwrap_or(1);
+        list_words.push(sentence[index-len..index].concat());
+        index-=len;
+    }
+    list_words.reverse();
+    list_words
+}
+
+///
+/// Word Segmentation Based on Forward Maximum Matching
+///
+pub fn fmm(sentence:&[&str],dict:&Dictionary)->Vec<String>{
+    fmm_with_max_len(sentence,dict,dict.max_word_len())
+}
 
+///
+/// Forward Maximum Matching trying words of at most `max_len` graphemes
+///
+pub fn fmm_with_max_len(sentence:&[&str],dict:&Dictionary,max_len:usize)->Vec<String>{
+    let mut list_words:Vec<String>=Vec::new();
+    let mut index=0;
+    while index<sentence.len(){
+        let len=dict.longest_prefix(&sentence[index..],max_len).unwrap_or(1);
+        list_words.push(sentence[index..index+len].concat());
+        index+=len;
     }
-    return list_words;
+    list_words
 }
 
 use std::io::{self, BufRead};
-use std::fs::{File, read_dir};
-use std::io::prelude::*;
+use std::fs::File;
 use std::path::Path;
 
 pub fn _read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
@@ -273,16 +788,23 @@ pub fn load_dictionary(filepath:&str)->Vec<String>{
 
     if let Ok(lines) = _read_lines(filepath) {
         // Consumes the iterator, returns an (Optional) String
-        for line in lines {
-            if let Ok(line) = line {
-                // println!("{}", ip);
-                //let word=line.replace("\n","").trim();
-                strings.push(String::from(line.replace("\n","").trim()));
-            }
+        for line in lines.map_while(Result::ok) {
+            strings.push(String::from(line.trim()));
         }
     }
     strings
 }
 
+///
+/// Same as `load_dictionary`, but returns the IO error when the file cannot be opened or read
+///
+pub fn try_load_dictionary(filepath:&str)->io::Result<Vec<String>>{
+    let mut strings=Vec::new();
+    for line in _read_lines(filepath)?{
+        strings.push(String::from(line?.trim()));
+    }
+    Ok(strings)
+}
+
 
 
@@ -10,156 +10,814 @@ mod tests{
         let summarized_text=summarize(text,stopwords,5);
         println!("{}",summarized_text);
     }
+
+    # [test]
+    fn test_ranked_sentences(){
+        let text="Rust is fast. Rust is safe and fast.  Cats sleep a lot. Rust is fun.";
+        let summarizer=Summarizer::new();
+        let ranked=summarizer.rank(text);
+        assert_eq!(ranked.len(),4);
+        for s in &ranked{
+            assert_eq!(&text[s.byte_start..s.byte_end],s.text);
+        }
+        assert_eq!(ranked[2].text,"Cats sleep a lot.");
+
+        // exactly the requested number of sentences
+        for n in 1..4{
+            assert_eq!(summarizer.summarize_ranked(text,SummaryLength::Sentences(n)).len(),n);
+        }
+        assert_eq!(summarizer.summarize_ranked(text,SummaryLength::Ratio(0.5)).len(),2);
+        let selected=summarizer.summarize_ranked(text,SummaryLength::Words(7));
+        assert!(selected.iter().map(|s| s.text.split_whitespace().count()).sum::<usize>()<=7);
+        assert!(!selected.is_empty());
+        assert!(summarizer.summarize_ranked(text,SummaryLength::Chars(5)).is_empty());
+        assert!(summarizer.rank("").is_empty());
+        let summary=summarizer.summarize(text,3);
+        assert_eq!(summary.matches('.').count(),3);
+        assert!(!summary.contains("Cats"));
+    }
+
+    # [test]
+    fn test_capitalised_stop_words(){
+        let text="Rust is fast. Rust is safe. Cats are fast.";
+        let scores=|summarizer:Summarizer| summarizer.rank(text).iter().map(|s| s.score).collect::<Vec<f64>>();
+        let lower=scores(Summarizer::new().stop_words(["rust","is"]));
+        assert_ne!(lower,scores(Summarizer::new()));
+        assert_eq!(scores(Summarizer::new().stop_words(["Rust","IS"])),lower);
+    }
+
+    # [test]
+    fn test_term_index(){
+        let sentences=vec![vec!["Rust","is","fast"],vec!["rust","is","safe","rust"],vec!["the","end"]];
+        let stop_words:HashSet<String>=vec!["is".to_string(),"the".to_string()].into_iter().collect();
+        let index=TermIndex::new(&sentences,&stop_words);
+        assert_eq!((index.len(),index.vocabulary_len()),(3,4));
+        assert_eq!(index.term_id("RUST"),Some(0));
+        assert_eq!(index.term_id("is"),None);
+        assert_eq!(index.vector(1).entries(),&[(0,2.0),(2,1.0)]);
+        let expected=2.0/(2.0_f64.sqrt()*5.0_f64.sqrt());
+        assert!((index.cosine(0,1)-expected).abs()<1e-12);
+        assert_eq!(index.cosine(0,2),0.0);
+        assert_eq!(SparseVector::new(Vec::new()).cosine(index.vector(0)),0.0);
+    }
+
+    # [test]
+    fn test_rankers(){
+        let sentences=vec![vec!["rust","fast","rust"],vec!["cat"],vec!["fast","dog"]];
+        let index=TermIndex::new(&sentences,&HashSet::new());
+        assert_eq!(Luhn::default().scores(&index),vec![3.0,0.0,1.0]);
+
+        // no similarity above the threshold leaves every sentence with the same score
+        let scores=LexRank{threshold:1.1,..Default::default()}.scores(&index);
+        assert!(scores.iter().all(|s| (s-scores[0]).abs()<1e-12));
+
+        let text="Rust is a fast and safe language. Rust is a fast and safe language. Rust programs are fast. Cats sleep all day.";
+        for ranker in [Box::new(TextRank) as Box<dyn SentenceRanker+Send+Sync>,Box::new(LexRank::default()),Box::new(Bm25TextRank::default()),Box::new(Luhn::default())]{
+            let summary=Summarizer{ranker,..Default::default()}.summarize_ranked(text,SummaryLength::Sentences(2));
+            assert_eq!(summary.len(),2);
+            assert!(summary.iter().all(|s| !s.text.starts_with("Cats")));
+        }
+
+        // TextRank picks the repeated sentence twice, redundancy removal only once
+        let summary=Summarizer::new().summarize_ranked(text,SummaryLength::Sentences(2));
+        assert_eq!(summary.iter().map(|s| s.index).collect::<Vec<usize>>(),vec![0,1]);
+        let summary=Summarizer::new().ranker(Mmr::new(TextRank,0.5)).summarize_ranked(text,SummaryLength::Sentences(2));
+        let indices:Vec<usize>=summary.iter().map(|s| s.index).collect();
+        assert_eq!(indices.len(),2);
+        assert_ne!(indices,vec![0,1]);
+    }
+
+    # [test]
+    #[cfg(feature = "parallel")]
+    fn test_parallel_similarity_matrix(){
+        let sentences:Vec<Vec<String>>=(0..300).map(|i| (0..8).map(|k| format!("w{}",(i*7+k*k)%40)).collect()).collect();
+        let index=TermIndex::new(&sentences,&HashSet::new());
+        assert_eq!(similarity_matrix(&index.vectors),sequential_similarity_matrix(&index.vectors));
+    }
+
+    # [test]
+    fn test_cjk_sentence_splitter(){
+        let text="我喜欢北京。你呢？“真的吗？”他说……\n第二段没有标点\n价格是1.5元! OK";
+        let sentences=CjkSentenceSplitter.split(text);
+        assert_eq!(sentences,vec!["我喜欢北京。","你呢？","“真的吗？”","他说……\n","第二段没有标点\n","价格是1.5元! ","OK"]);
+        assert_eq!(sentences.concat(),text);
+        assert!(CjkSentenceSplitter.split("").is_empty());
+    }
+
+    # [test]
+    fn test_pluggable_tokenizer(){
+        let text="苹果很好吃。我喜欢苹果。今天下雨。";
+        let by_char=|s:&str| -> Vec<String> { s.chars().filter(|c| c.is_alphanumeric()).map(String::from).collect() };
+        let summarizer=Summarizer::new().splitter(CjkSentenceSplitter).tokenizer(by_char);
+        let summary=summarizer.summarize(text,1);
+        assert!(summary.contains("苹果"));
+    }
 }
 
 use unicode_segmentation::UnicodeSegmentation;
-use std::collections::BTreeSet;
+use std::collections::{HashMap, HashSet};
 use ndarray::{Array1, Array2};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use crate::native::keywords::IdfTable;
+use crate::native::segmentation::Segmenter;
 
 ///
-/// Summarize text
+/// Splits a text into sentences
 ///
-pub fn summarize(text: &str, stop_words: &[&str], num_sentence: usize) -> String {
-    let sentences = text.unicode_sentences().collect::<Vec<&str>>();
-    if num_sentence >= sentences.len() {
-        return text.to_string();
-    }
-    let mut sentences_and_words = vec![];
-    sentences.iter().for_each(|&sentence| {
-        let words = split_into_words(sentence);
-        sentences_and_words.push(words);
-    });
-    let matrix = build_similarity_matrix(&sentences_and_words, stop_words);
-    let ranks = calculate_sentence_rank(&matrix);
-    let mut sorted_ranks = ranks.clone();
-    sorted_ranks.sort_by(|a, b| b.partial_cmp(a).unwrap());
-    let least_rank = sorted_ranks[num_sentence + 1];
-    let mut result: Vec<&str> = vec![];
-    let mut included_count = 0;
-    for i in 0..sentences.len() {
-        if ranks[i] >= least_rank {
-            included_count = included_count + 1;
-            result.push(sentences[i]);
-        }
-        if included_count == num_sentence {
-            break;
+pub trait SentenceSplitter {
+    /// Sentences as consecutive slices of the text, so that they concatenate back to it
+    fn split<'a>(&self, text: &'a str) -> Vec<&'a str>;
+}
+
+///
+/// Splits a sentence into the words compared between sentences
+///
+pub trait Tokenizer {
+    fn tokenize(&self, sentence: &str) -> Vec<String>;
+}
+
+///
+/// Unicode sentence boundaries (UAX #29), suited to languages separating sentences with spaces
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnicodeSentenceSplitter;
+
+impl SentenceSplitter for UnicodeSentenceSplitter {
+    fn split<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        text.unicode_sentences().collect()
+    }
+}
+
+///
+/// Sentence splitter for Chinese and Japanese text. A sentence ends after a run of 。！？!?… or a "." followed by a space,
+/// with the closing quotes or brackets after it, or at a line break. Opening quotes start the next sentence.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CjkSentenceSplitter;
+
+impl SentenceSplitter for CjkSentenceSplitter {
+    fn split<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        let is_end = |c: char| matches!(c, '。' | '！' | '？' | '!' | '?' | '…' | '．' | '｡');
+        let is_close = |c: char| matches!(c, '”' | '’' | '」' | '』' | '）' | ')' | '》' | '"' | '\'' | '】');
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+        let mut sentences = Vec::new();
+        let (mut start, mut i) = (0, 0);
+        while i < chars.len() {
+            let c = chars[i].1;
+            let next_is_space = chars.get(i + 1).map(|n| n.1.is_whitespace()).unwrap_or(true);
+            i += 1;
+            if c == '\n' || is_end(c) || (c == '.' && next_is_space) {
+                while i < chars.len() && (is_end(chars[i].1) || is_close(chars[i].1)) {
+                    i += 1;
+                }
+                while i < chars.len() && chars[i].1.is_whitespace() {
+                    i += 1;
+                }
+                let end = chars.get(i).map(|c| c.0).unwrap_or(text.len());
+                sentences.push(&text[start..end]);
+                start = end;
+            }
+        }
+        if start < text.len() {
+            sentences.push(&text[start..]);
         }
+        sentences
     }
-    result.join("")
 }
 
-fn get_all_words_lc<'a>(sentence1: &[&'a str], sentence2: &[&'a str]) -> BTreeSet<String> {
-    let mut all_words: BTreeSet<String> = BTreeSet::new();
+impl<F> SentenceSplitter for F
+where
+    F: for<'a> Fn(&'a str) -> Vec<&'a str>,
+{
+    fn split<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        self(text)
+    }
+}
 
-    sentence1.iter().for_each(|w| {
-        all_words.insert(w.to_lowercase());
-    });
+///
+/// Unicode word boundaries (UAX #29), which gives single characters for Chinese and Japanese
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnicodeWordTokenizer;
 
-    sentence2.iter().for_each(|w| {
-        all_words.insert(w.to_lowercase());
-    });
-    return all_words;
+impl Tokenizer for UnicodeWordTokenizer {
+    fn tokenize(&self, sentence: &str) -> Vec<String> {
+        sentence.unicode_words().map(String::from).collect()
+    }
 }
 
 ///
-/// Retrieve a sentence vector based on the frequency of words that appears in the all_words_lc set.
-/// all_words_lc should be a sorted set of lower cased words
-/// The size of the resulting vector is the same as the all_words_lc set
-/// stop_words are skipped
+/// Dictionary-based words for languages written without spaces. Punctuation and whitespace are left out.
 ///
-fn get_sentence_vector(sentence: &[&str], all_words_lc: &BTreeSet<String>, stop_words: &[&str]) -> Vec<usize> {
-    let mut vector: Vec<usize> = vec![0; all_words_lc.len()];
-    for word in sentence {
-        let word_lc = word.to_lowercase();
-        if !stop_words.contains(&word_lc.as_str()) {
-            let index = all_words_lc.iter().position(|x| x.eq(&word_lc)).unwrap();
-            vector[index] += 1;
-        }
+impl Tokenizer for Segmenter {
+    fn tokenize(&self, sentence: &str) -> Vec<String> {
+        self.cut(sentence).into_iter().filter(|w| w.chars().any(char::is_alphanumeric)).collect()
+    }
+}
+
+impl<F> Tokenizer for F
+where
+    F: Fn(&str) -> Vec<String>,
+{
+    fn tokenize(&self, sentence: &str) -> Vec<String> {
+        self(sentence)
     }
-    return vector;
 }
 
 ///
-/// Calculates the cosine distance between two vectors
-/// Refer to [YouTube](https://www.youtube.com/watch?v=3X0wLRwU_Ws)
+/// An extractive summarizer ranking sentences with TextRank by default. The sentence splitter, the tokenizer
+/// and the ranking strategy can be replaced, e.g. by `CjkSentenceSplitter` and a `Segmenter` for Chinese documents.
 ///
-fn cosine_distance(vec1: &Vec<usize>, vec2: &Vec<usize>) -> f64 {
-    let dot_product = dot_product(vec1, vec2);
-    let root_sum_square1 = root_sum_square(vec1);
-    let root_sum_square2 = root_sum_square(vec2);
-    return dot_product as f64 / (root_sum_square1 * root_sum_square2);
+pub struct Summarizer {
+    splitter: Box<dyn SentenceSplitter + Send + Sync>,
+    tokenizer: Box<dyn Tokenizer + Send + Sync>,
+    ranker: Box<dyn SentenceRanker + Send + Sync>,
+    stop_words: HashSet<String>,
 }
 
-fn root_sum_square(vec: &Vec<usize>) -> f64 {
-    let mut sum_square = 0;
-    for i in 0..vec.len() {
-        sum_square += vec[i] * vec[i];
+impl Default for Summarizer {
+    fn default() -> Summarizer {
+        Summarizer {
+            splitter: Box::new(UnicodeSentenceSplitter),
+            tokenizer: Box::new(UnicodeWordTokenizer),
+            ranker: Box::new(TextRank),
+            stop_words: HashSet::new(),
+        }
     }
-    (sum_square as f64).sqrt()
 }
 
-fn dot_product(vec1: &Vec<usize>, vec2: &Vec<usize>) -> usize {
-    let delta = vec1.len() - vec2.len();
-    let shortest_vec = match delta {
-        d if d < 0 => vec1,
-        d if d > 0 => vec2,
-        _ => vec1
-    };
-    let mut dot_product = 0;
-    for i in 0..shortest_vec.len() {
-        dot_product += vec1[i] * vec2[i];
+impl Summarizer {
+    ///
+    /// A summarizer splitting sentences and words on Unicode boundaries, without stop words
+    ///
+    pub fn new() -> Summarizer {
+        Summarizer::default()
+    }
+
+    pub fn splitter<S: SentenceSplitter + Send + Sync + 'static>(mut self, splitter: S) -> Summarizer {
+        self.splitter = Box::new(splitter);
+        self
+    }
+
+    pub fn tokenizer<T: Tokenizer + Send + Sync + 'static>(mut self, tokenizer: T) -> Summarizer {
+        self.tokenizer = Box::new(tokenizer);
+        self
+    }
+
+    ///
+    /// Strategy scoring and picking sentences: `TextRank`, `LexRank`, `Bm25TextRank`, `Luhn` or `Mmr`
+    ///
+    pub fn ranker<R: SentenceRanker + Send + Sync + 'static>(mut self, ranker: R) -> Summarizer {
+        self.ranker = Box::new(ranker);
+        self
+    }
+
+    ///
+    /// Words left out of the comparison of sentences, lower-cased so that matching ignores case
+    ///
+    pub fn stop_words<I, S>(mut self, words: I) -> Summarizer
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.stop_words.extend(words.into_iter().map(|word| word.into().to_lowercase()));
+        self
+    }
+
+    ///
+    /// Keep the `num_sentence` best ranked sentences in their original order
+    ///
+    pub fn summarize(&self, text: &str, num_sentence: usize) -> String {
+        let sentences = self.splitter.split(text);
+        if num_sentence >= sentences.len() {
+            return text.to_string();
+        }
+        let selected = self.select(&sentences, SummaryLength::Sentences(num_sentence));
+        selected.iter().map(|s| sentences[s.index]).collect()
+    }
+
+    ///
+    /// Every sentence of the text with its score, in their original order
+    ///
+    pub fn rank(&self, text: &str) -> Vec<RankedSentence> {
+        let sentences = self.splitter.split(text);
+        self.rank_sentences(&sentences).1
+    }
+
+    ///
+    /// The best ranked sentences fitting `length`, in their original order
+    ///
+    pub fn summarize_ranked(&self, text: &str, length: SummaryLength) -> Vec<RankedSentence> {
+        let sentences = self.splitter.split(text);
+        self.select(&sentences, length)
+    }
+
+    fn select(&self, sentences: &[&str], length: SummaryLength) -> Vec<RankedSentence> {
+        let (index, ranked) = self.rank_sentences(sentences);
+        let scores: Vec<f64> = ranked.iter().map(|s| s.score).collect();
+        let order = self.ranker.order(&index, &scores);
+        // at most this many sentences, or a budget and the size of each sentence
+        let (max_sentences, budget) = match length {
+            SummaryLength::Sentences(n) => (n, None),
+            SummaryLength::Ratio(ratio) => ((ranked.len() as f64 * ratio.clamp(0.0, 1.0)).round().max(1.0) as usize, None),
+            SummaryLength::Words(n) => (ranked.len(), Some((n, ranked.iter().map(|s| self.tokenizer.tokenize(&s.text).len()).collect::<Vec<usize>>()))),
+            SummaryLength::Chars(n) => (ranked.len(), Some((n, ranked.iter().map(|s| s.text.chars().count()).collect()))),
+        };
+        let mut keep = vec![false; ranked.len()];
+        let (mut kept, mut used) = (0, 0);
+        for i in order {
+            if kept == max_sentences {
+                break;
+            }
+            if let Some((limit, sizes)) = &budget {
+                if used + sizes[i] > *limit {
+                    continue;
+                }
+                used += sizes[i];
+            }
+            keep[i] = true;
+            kept += 1;
+        }
+        ranked.into_iter().filter(|s| keep[s.index]).collect()
+    }
+
+    fn rank_sentences(&self, sentences: &[&str]) -> (TermIndex, Vec<RankedSentence>) {
+        let sentences_and_words: Vec<Vec<String>> = sentences.iter().map(|s| self.tokenizer.tokenize(s)).collect();
+        let index = TermIndex::new(&sentences_and_words, &self.stop_words);
+        if index.is_empty() {
+            return (index, Vec::new());
+        }
+        let ranks = self.ranker.scores(&index);
+        let mut start = 0;
+        let ranked = sentences
+            .iter()
+            .zip(ranks)
+            .enumerate()
+            .map(|(index, (sentence, score))| {
+                let byte_start = start + (sentence.len() - sentence.trim_start().len());
+                let text = sentence.trim();
+                start += sentence.len();
+                RankedSentence { index, byte_start, byte_end: byte_start + text.len(), score, text: text.to_string() }
+            })
+            .collect();
+        (index, ranked)
     }
-    dot_product
 }
 
-fn sentence_similarity(s1: &[&str], s2: &[&str], stop_words: &[&str]) -> f64 {
-    let all_words = get_all_words_lc(s1, s2);
-    let v1 = get_sentence_vector(s1, &all_words, stop_words);
-    let v2 = get_sentence_vector(s2, &all_words, stop_words);
-    1.0 - cosine_distance(&v1, &v2)
+///
+/// A sentence of the summarized text
+///
+/// - `index`: position of the sentence in the text,
+/// - `byte_start`, `byte_end`: byte offsets of the sentence without surrounding whitespace, so that `&text[byte_start..byte_end]` is `text`,
+/// - `score`: the rank of the sentence, higher is more central to the text.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankedSentence {
+    pub index: usize,
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub score: f64,
+    pub text: String,
 }
 
 ///
-/// Calculate a similarity matrix for the given sentences.
-/// Returns a 2-D array M_i,j such that for all 'j', sum(i, M_i,j) = 1
-/// We take a leap of faith here and assume that cosine similarity is similar to the probability
-/// that a sentence is important for summarization
+/// Length of a summary
 ///
-fn build_similarity_matrix(sentences: &Vec<Vec<&str>>, stop_words: &[&str]) -> Array2<f64> {
-    let len = sentences.len();
-    let mut matrix = Array2::<f64>::zeros((len, len));
-    let mut sum_column: Vec<f64> = vec![0.0; len];
-    for i in 0..len {
-        for j in 0..len {
-            if i == j {
-                continue;
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SummaryLength {
+    /// Number of sentences
+    Sentences(usize),
+    /// Share of the sentences of the text, e.g. 0.2 keeps 20%, at least one sentence
+    Ratio(f64),
+    /// Maximum number of words given by the tokenizer; best sentences that do not fit are skipped
+    Words(usize),
+    /// Maximum number of characters; best sentences that do not fit are skipped
+    Chars(usize),
+}
+
+///
+/// Summarize text
+///
+pub fn summarize(text: &str, stop_words: &[&str], num_sentence: usize) -> String {
+    Summarizer::new().stop_words(stop_words.iter().copied()).summarize(text, num_sentence)
+}
+
+///
+/// Sparse term vectors of the sentences of a document over one vocabulary, so that comparing two sentences
+/// only walks the words they contain. Words are lower-cased and stop words left out.
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TermIndex {
+    terms: HashMap<String, usize>,
+    words: Vec<String>,
+    sentences: Vec<Vec<usize>>,
+    vectors: Vec<SparseVector>,
+    document_frequency: Vec<usize>,
+}
+
+///
+/// Weights of the terms of a sentence, sorted by term id
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SparseVector {
+    entries: Vec<(usize, f64)>,
+    norm: f64,
+}
+
+impl TermIndex {
+    ///
+    /// Index tokenized sentences, weighting each term by its count in the sentence
+    ///
+    pub fn new<W: AsRef<str>>(sentences: &[Vec<W>], stop_words: &HashSet<String>) -> TermIndex {
+        let mut index = TermIndex::default();
+        for words in sentences {
+            let mut ids = Vec::with_capacity(words.len());
+            for word in words {
+                let word_lc = word.as_ref().to_lowercase();
+                if stop_words.contains(&word_lc) {
+                    continue;
+                }
+                let id = match index.terms.get(&word_lc) {
+                    Some(&id) => id,
+                    None => {
+                        index.words.push(word_lc.clone());
+                        index.document_frequency.push(0);
+                        index.terms.insert(word_lc, index.words.len() - 1);
+                        index.words.len() - 1
+                    }
+                };
+                ids.push(id);
             }
-            matrix[[i, j]] = sentence_similarity(sentences[i].as_slice(), sentences[j].as_slice(), stop_words);
+            let mut counts: HashMap<usize, f64> = HashMap::new();
+            for &id in &ids {
+                *counts.entry(id).or_insert(0.0) += 1.0;
+            }
+            for &id in counts.keys() {
+                index.document_frequency[id] += 1;
+            }
+            index.vectors.push(SparseVector::new(counts.into_iter().collect()));
+            index.sentences.push(ids);
         }
+        index
+    }
+
+    ///
+    /// Number of sentences
+    ///
+    pub fn len(&self) -> usize {
+        self.vectors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vectors.is_empty()
     }
-    // at this point we have the cosine similarity of each sentence.
-    // take a leap of faith and assume that the cosine similarity is the probability that a sentence
+
+    pub fn vocabulary_len(&self) -> usize {
+        self.terms.len()
+    }
+
+    pub fn term_id(&self, word: &str) -> Option<usize> {
+        self.terms.get(&word.to_lowercase()).copied()
+    }
+
+    pub fn term(&self, id: usize) -> &str {
+        &self.words[id]
+    }
+
+    ///
+    /// Term ids of the words of a sentence in their order, without stop words
+    ///
+    pub fn terms(&self, sentence: usize) -> &[usize] {
+        &self.sentences[sentence]
+    }
+
+    ///
+    /// Number of sentences containing the term
+    ///
+    pub fn document_frequency(&self, id: usize) -> usize {
+        self.document_frequency[id]
+    }
+
+    pub fn vector(&self, sentence: usize) -> &SparseVector {
+        &self.vectors[sentence]
+    }
+
+    pub fn cosine(&self, a: usize, b: usize) -> f64 {
+        self.vectors[a].cosine(&self.vectors[b])
+    }
+}
+
+impl SparseVector {
+    ///
+    /// A vector from (term id, weight) pairs in any order; a term must appear once
+    ///
+    pub fn new(mut entries: Vec<(usize, f64)>) -> SparseVector {
+        entries.sort_unstable_by_key(|e| e.0);
+        let norm = entries.iter().map(|e| e.1 * e.1).sum::<f64>().sqrt();
+        SparseVector { entries, norm }
+    }
+
+    pub fn entries(&self) -> &[(usize, f64)] {
+        &self.entries
+    }
+
+    pub fn norm(&self) -> f64 {
+        self.norm
+    }
+
+    pub fn dot(&self, other: &SparseVector) -> f64 {
+        let (mut i, mut j, mut sum) = (0, 0, 0.0);
+        while i < self.entries.len() && j < other.entries.len() {
+            let (a, b) = (self.entries[i], other.entries[j]);
+            match a.0.cmp(&b.0) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    sum += a.1 * b.1;
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        sum
+    }
+
+    ///
+    /// Cosine similarity, 0 when one of the vectors is empty
+    /// Refer to [YouTube](https://www.youtube.com/watch?v=3X0wLRwU_Ws)
+    ///
+    pub fn cosine(&self, other: &SparseVector) -> f64 {
+        if self.norm == 0.0 || other.norm == 0.0 {
+            return 0.0;
+        }
+        self.dot(other) / (self.norm * other.norm)
+    }
+}
+
+///
+/// Calculate the cosine similarity matrix of the given sentence vectors, with zeros on the diagonal
+///
+fn similarity_matrix(vectors: &[SparseVector]) -> Array2<f64> {
+    // rows are computed in parallel with the `parallel` feature
+    #[cfg(feature = "parallel")]
+    let matrix = symmetric_matrix((0..vectors.len()).into_par_iter().map(|i| upper_row(vectors, i)).collect());
+    #[cfg(not(feature = "parallel"))]
+    let matrix = sequential_similarity_matrix(vectors);
+    matrix
+}
+
+#[cfg(any(test, not(feature = "parallel")))]
+fn sequential_similarity_matrix(vectors: &[SparseVector]) -> Array2<f64> {
+    symmetric_matrix((0..vectors.len()).map(|i| upper_row(vectors, i)).collect())
+}
+
+/// Similarities of sentence `i` with the sentences after it: row `i` of the upper triangle
+fn upper_row(vectors: &[SparseVector], i: usize) -> Vec<f64> {
+    (i + 1..vectors.len()).map(|j| vectors[i].cosine(&vectors[j])).collect()
+}
+
+fn symmetric_matrix(rows: Vec<Vec<f64>>) -> Array2<f64> {
+    let len = rows.len();
+    let mut matrix = Array2::<f64>::zeros((len, len));
+    for (i, row) in rows.into_iter().enumerate() {
+        for (k, similarity) in row.into_iter().enumerate() {
+            matrix[[i, i + 1 + k]] = similarity;
+            matrix[[i + 1 + k, i]] = similarity;
+        }
+    }
+    matrix
+}
+
+///
+/// Make a similarity matrix M_i,j such that for all 'j', sum(i, M_i,j) = 1
+///
+fn normalize_columns(matrix: &mut Array2<f64>) {
+    // take a leap of faith and assume that the similarity is the probability that a sentence
     // is important for summarization.
     // We do this by normalizing the matrix along the column. The column values should add up to 1.
-    for j in 0..len {
-        let mut sum: f64 = 0.0;
+    for mut column in matrix.columns_mut() {
+        let sum = column.sum();
+        if sum > 0.0 {
+            column /= sum;
+        }
+    }
+}
+
+///
+/// A strategy giving each sentence a score and the order in which sentences are picked for a summary
+///
+pub trait SentenceRanker {
+    ///
+    /// Score of every sentence of the index, higher is better
+    ///
+    fn scores(&self, index: &TermIndex) -> Vec<f64>;
+
+    ///
+    /// Sentences from the first to pick to the last, by default best score first and earlier sentences first on ties
+    ///
+    fn order(&self, index: &TermIndex, scores: &[f64]) -> Vec<usize> {
+        let _ = index;
+        let mut order: Vec<usize> = (0..scores.len()).collect();
+        order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]).then(a.cmp(&b)));
+        order
+    }
+}
+
+///
+/// PageRank over the cosine similarity of word counts, as in TextRank (Mihalcea and Tarau, 2004)
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextRank;
+
+impl SentenceRanker for TextRank {
+    fn scores(&self, index: &TermIndex) -> Vec<f64> {
+        let mut matrix = similarity_matrix(&index.vectors);
+        normalize_columns(&mut matrix);
+        calculate_sentence_rank(&matrix)
+    }
+}
+
+///
+/// LexRank (Erkan and Radev, 2004): PageRank over the cosine similarity of TF-IDF vectors.
+///
+/// - `threshold`: similarities below it are dropped,
+/// - `continuous`: keep the similarities as edge weights; otherwise every remaining edge weighs 1,
+/// - `idf`: IDF of the words, e.g. computed on a corpus with `IdfTable::from_documents`; `None` treats the sentences
+///   of the document as the documents.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexRank {
+    pub threshold: f64,
+    pub continuous: bool,
+    pub idf: Option<IdfTable>,
+}
+
+impl Default for LexRank {
+    fn default() -> LexRank {
+        LexRank { threshold: 0.1, continuous: false, idf: None }
+    }
+}
+
+impl SentenceRanker for LexRank {
+    fn scores(&self, index: &TermIndex) -> Vec<f64> {
+        let n = index.len() as f64;
+        let idf = |term: usize| match &self.idf {
+            Some(table) => table.idf(index.term(term)),
+            None => ((1.0 + n) / (1.0 + index.document_frequency(term) as f64)).ln() + 1.0,
+        };
+        let vectors: Vec<SparseVector> = index
+            .vectors
+            .iter()
+            .map(|v| SparseVector::new(v.entries().iter().map(|&(term, tf)| (term, tf * idf(term))).collect()))
+            .collect();
+        let mut matrix = similarity_matrix(&vectors);
+        matrix.mapv_inplace(|s| match s {
+            s if s < self.threshold || s == 0.0 => 0.0,
+            s if self.continuous => s,
+            _ => 1.0,
+        });
+        normalize_columns(&mut matrix);
+        calculate_sentence_rank(&matrix)
+    }
+}
+
+///
+/// TextRank with the BM25 score of one sentence against another as edge weight (Barrios et al., 2016)
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bm25TextRank {
+    pub k1: f64,
+    pub b: f64,
+}
+
+impl Default for Bm25TextRank {
+    fn default() -> Bm25TextRank {
+        Bm25TextRank { k1: 1.2, b: 0.75 }
+    }
+}
+
+impl SentenceRanker for Bm25TextRank {
+    fn scores(&self, index: &TermIndex) -> Vec<f64> {
+        let len = index.len();
+        let n = len as f64;
+        let average_len = (0..len).map(|i| index.terms(i).len()).sum::<usize>() as f64 / n;
+        let idf: Vec<f64> = (0..index.vocabulary_len())
+            .map(|term| {
+                let df = index.document_frequency(term) as f64;
+                (1.0 + (n - df + 0.5) / (df + 0.5)).ln()
+            })
+            .collect();
+        // weight of the link from sentence j to sentence i: BM25 of sentence i for the words of sentence j
+        let mut matrix = Array2::<f64>::zeros((len, len));
         for i in 0..len {
-            if i == j {
-                continue;
+            let document = index.vector(i);
+            let length_norm = self.k1 * (1.0 - self.b + self.b * index.terms(i).len() as f64 / average_len.max(1.0));
+            for j in (0..len).filter(|&j| j != i) {
+                let query = index.vector(j);
+                let mut score = 0.0;
+                for &(term, tf) in document.entries() {
+                    if query.entries().binary_search_by_key(&term, |e| e.0).is_ok() {
+                        score += idf[term] * tf * (self.k1 + 1.0) / (tf + length_norm);
+                    }
+                }
+                matrix[[i, j]] = score;
             }
-            sum += matrix[[i, j]];
         }
-        sum_column[j] = sum;
+        normalize_columns(&mut matrix);
+        calculate_sentence_rank(&matrix)
     }
-    for i in 0..len {
-        for j in 0..len {
-            if i == j {
-                continue;
+}
+
+///
+/// Luhn's method (1958): words occurring at least `min_frequency` times in the document are significant,
+/// and a sentence scores the best of its clusters, i.e. runs of words where significant words are at most
+/// `max_gap` words apart, as (significant words)² / (cluster length).
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Luhn {
+    pub min_frequency: usize,
+    pub max_gap: usize,
+}
+
+impl Default for Luhn {
+    fn default() -> Luhn {
+        Luhn { min_frequency: 2, max_gap: 4 }
+    }
+}
+
+impl SentenceRanker for Luhn {
+    fn scores(&self, index: &TermIndex) -> Vec<f64> {
+        let mut frequency = vec![0; index.vocabulary_len()];
+        for i in 0..index.len() {
+            for &term in index.terms(i) {
+                frequency[term] += 1;
             }
-            matrix[[i, j]] = matrix[[i, j]] / sum_column[j];
         }
+        (0..index.len())
+            .map(|i| {
+                let positions: Vec<usize> =
+                    index.terms(i).iter().enumerate().filter(|(_, &t)| frequency[t] >= self.min_frequency).map(|(p, _)| p).collect();
+                let mut best: f64 = 0.0;
+                let mut start = 0;
+                for k in 0..positions.len() {
+                    let cluster_ends = k + 1 == positions.len() || positions[k + 1] - positions[k] > self.max_gap + 1;
+                    if cluster_ends {
+                        let significant = (k + 1 - start) as f64;
+                        let length = (positions[k] - positions[start] + 1) as f64;
+                        best = best.max(significant * significant / length);
+                        start = k + 1;
+                    }
+                }
+                best
+            })
+            .collect()
+    }
+}
+
+///
+/// Maximal marginal relevance (Carbonell and Goldstein, 1998) on top of another ranker: sentences are picked one at a time,
+/// trading the score of the ranker (scaled to 0..1) for dissimilarity to the sentences already picked.
+/// `lambda` is 1 for the ranker order alone and lower to remove more redundancy.
+///
+pub struct Mmr {
+    ranker: Box<dyn SentenceRanker + Send + Sync>,
+    lambda: f64,
+}
+
+impl Mmr {
+    pub fn new<R: SentenceRanker + Send + Sync + 'static>(ranker: R, lambda: f64) -> Mmr {
+        Mmr { ranker: Box::new(ranker), lambda }
+    }
+}
+
+impl SentenceRanker for Mmr {
+    fn scores(&self, index: &TermIndex) -> Vec<f64> {
+        self.ranker.scores(index)
+    }
+
+    fn order(&self, index: &TermIndex, scores: &[f64]) -> Vec<usize> {
+        let max = scores.iter().copied().fold(0.0, f64::max);
+        let relevance: Vec<f64> = scores.iter().map(|s| if max > 0.0 { s / max } else { 0.0 }).collect();
+        let mut remaining: Vec<usize> = (0..scores.len()).collect();
+        // highest similarity of each remaining sentence to the picked ones
+        let mut redundancy = vec![0.0; scores.len()];
+        let mut order = Vec::with_capacity(scores.len());
+        while !remaining.is_empty() {
+            let marginal = |i: usize| self.lambda * relevance[i] - (1.0 - self.lambda) * redundancy[i];
+            let (position, &best) = remaining
+                .iter()
+                .enumerate()
+                .max_by(|a, b| marginal(*a.1).total_cmp(&marginal(*b.1)).then(b.1.cmp(a.1)))
+                .unwrap();
+            remaining.remove(position);
+            order.push(best);
+            for &i in &remaining {
+                redundancy[i] = f64::max(redundancy[i], index.cosine(i, best));
+            }
+        }
+        order
     }
-    matrix
 }
 
 ///
@@ -193,12 +851,3 @@ fn calculate_sentence_rank(similarity_matrix: &Array2<f64>) -> Vec<f64> {
     }
     result.into_raw_vec()
 }
-
-fn split_into_words(sentence: &str) -> Vec<&str> {
-    let mut result = vec![];
-    let words = sentence.unicode_words();
-    for word in words {
-        result.push(word);
-    }
-    result
-}
\ No newline at end of file
@@ -1,11 +1,13 @@
-use std::io;
-use std::io::prelude::*;
+use log::trace;
 use crate::native::toksiter::*;
 use crate::native::chardata::*;
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
+    #[allow(unused_imports)]
     use std::borrow::Borrow;
+    #[allow(unused_imports)]
     use crate::native::word2vec::*;
     use crate::native::toksiter::*;
     use crate::native::chardata::*;
@@ -13,6 +15,7 @@ mod tests {
 
     # [test]
     fn token_analyze(){
+        #[allow(unused_mut)]
         let mut s="hello world!";
 
         let mut chs = s.chars();
@@ -43,41 +46,14 @@ pub fn get_token_list(s:&str)->Vec<Token>{
 
     let mut chs = s.chars();
     let mut chds = CharDataIter::new(&mut chs);
-    let mut toks = TokenIter::new(&mut chds);
+    let toks = TokenIter::new(&mut chds);
     let mut list_token:Vec<Token>=Vec::new();
-    // Run the tokenizer, dump debug info for each token:
-    loop {
-        match toks.next() {
-            Some(tok) => {
-                println!("{:?}", tok);
-                list_token.push(tok);
-            },
-            None => { println!("<END_OF_TEXT>"); break; }
-        }
+    // Run the tokenizer, trace each token for debugging:
+    for tok in toks {
+        trace!("{:?}", tok);
+        list_token.push(tok);
     }
+    trace!("<END_OF_TEXT>");
     list_token
 
 }
-
-
-
-fn main() {
-    // Get stdin into a string
-    let stdin = io::stdin();
-    let mut s = String::new();
-    stdin.lock().read_to_string(&mut s).unwrap();
-    println!("{}", s);
-
-    // Construct a tokenizer by adapting some more primitive iterators
-    let mut chs = s.chars();
-    let mut chds = CharDataIter::new(&mut chs);
-    let mut toks = TokenIter::new(&mut chds);
-
-    // Run the tokenizer, dump debug info for each token:
-    loop {
-        match toks.next() {
-            Some(tok) => { println!("{:?}", tok) },
-            None => { println!("<END_OF_TEXT>"); break; }
-        }
-    }
-}
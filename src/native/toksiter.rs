@@ -49,13 +49,7 @@ impl<'a> TokenIter<'a> {
     }
 
     fn is_boundary_char(ch: char) -> bool {
-        if ch == chardata::END_OF_STRING {
-            true
-        } else if ch.is_whitespace() {
-            true
-        } else {
-            false
-        }
+        ch == chardata::END_OF_STRING || ch.is_whitespace()
     }
 }
 
@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use unicode_segmentation::UnicodeSegmentation;
+use crate::error::{Error, Result};
+use crate::native::dictionary::Dictionary;
+use crate::native::hmm::{Hmm, BMES, UNKNOWN_OBSERVATION};
+
+#[cfg(test)]
+mod tests {
+    use crate::native::trainer::*;
+
+    const CORPUS: &str = "我 喜欢 北京
+北京 是 首都
+我 爱 吃 苹果
+";
+
+    #[test]
+    fn test_train() {
+        let mut trainer = Trainer::new();
+        for line in CORPUS.lines() {
+            trainer.add_line(line);
+        }
+        assert_eq!(trainer.sentences(), 3);
+        let model = trainer.build();
+        assert_eq!(model.dictionary.freq("北京"), Some(2));
+        assert_eq!(model.dictionary.freq("我"), Some(2));
+        assert_eq!(model.dictionary.total_freq(), 10);
+        // "北京" is always B then E, so the model joins it when it is unknown
+        assert_eq!(model.hmm.cut(&["北", "京"]), vec!["北京"]);
+    }
+
+    #[test]
+    fn test_train_tagged() {
+        let mut trainer = Trainer::new();
+        trainer.add_tagged_line("我/r 喜欢/v 北京/ns");
+        trainer.add_tagged_line("我/r 爱/v 天安门/ns");
+        trainer.add_tagged_line("北京/ns 是/v 首都/n");
+        let model = trainer.build();
+        assert_eq!(model.dictionary.pos("北京"), Some("ns"));
+        assert_eq!(model.dictionary.freq("北京"), Some(2));
+        let pos_hmm = model.pos_hmm.as_ref().unwrap();
+        assert_eq!(pos_hmm.viterbi(&["我", "喜欢", "上海"]), vec!["r", "v", "ns"]);
+        let parsed = SegmentationModel::parse(&model.to_string()).unwrap();
+        assert_eq!(parsed.pos_hmm.as_ref(), Some(pos_hmm));
+    }
+
+    #[test]
+    fn test_model_round_trip() {
+        let mut trainer = Trainer::new();
+        for line in CORPUS.lines() {
+            trainer.add_line(line);
+        }
+        let model = trainer.build();
+        let parsed = SegmentationModel::parse(&model.to_string()).unwrap();
+        assert_eq!(parsed.dictionary.freq("苹果"), Some(1));
+        assert_eq!(parsed.dictionary.len(), model.dictionary.len());
+        assert_eq!(parsed.hmm, model.hmm);
+        assert!(parsed.pos_hmm.is_none());
+        assert!(SegmentationModel::parse("word\t北京").is_err());
+    }
+}
+
+///
+/// A segmentation model: a frequency dictionary for the dictionary methods and a BMES HMM for unknown words.
+/// A model trained on a POS-tagged corpus also has the tag of each word and a tag HMM for unknown words.
+///
+/// The model file holds one tab-separated entry per line: `word\t北京\t2\tns` for dictionary entries,
+/// followed by the `start`, `trans` and `emit` lines of the `Hmm`, and the lines of the tag HMM prefixed by `pos\t`.
+///
+#[derive(Debug, Clone, Default)]
+pub struct SegmentationModel {
+    pub dictionary: Dictionary,
+    pub hmm: Hmm,
+    /// Part-of-speech tags as states and words as observations
+    pub pos_hmm: Option<Hmm>,
+}
+
+impl SegmentationModel {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<SegmentationModel> {
+        SegmentationModel::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(input: &str) -> Result<SegmentationModel> {
+        let mut dictionary = Dictionary::new();
+        let mut hmm = Hmm::new(&BMES);
+        let mut pos_hmm: Option<Hmm> = None;
+        for (n, line) in input.lines().enumerate() {
+            let err = |msg: String| Error::Parse(format!("model line {}: {}", n + 1, msg));
+            if let Some(entry) = line.strip_prefix("pos\t") {
+                pos_hmm.get_or_insert_with(|| Hmm::new(&[])).parse_line(entry).map_err(err)?;
+                continue;
+            }
+            match line.strip_prefix("word\t") {
+                Some(entry) => {
+                    let fields: Vec<&str> = entry.split('\t').collect();
+                    match fields.as_slice() {
+                        [word, freq] | [word, freq, _] => {
+                            let freq = freq.trim().parse().map_err(|_| err(format!("invalid frequency {:?}", freq)))?;
+                            dictionary.insert_entry(word, freq, fields.get(2).map(|p| p.trim()));
+                        }
+                        _ => return Err(err(format!("expected word and frequency, got {:?}", line))),
+                    }
+                }
+                None => hmm.parse_line(line).map_err(err)?,
+            }
+        }
+        Ok(SegmentationModel { dictionary, hmm, pos_hmm })
+    }
+
+    pub fn write_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for SegmentationModel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# rsnltk segmentation model")?;
+        let mut words: Vec<(String, u64, Option<String>)> = self.dictionary.entries();
+        words.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        for (word, freq, pos) in words {
+            match pos {
+                Some(pos) => writeln!(f, "word\t{}\t{}\t{}", word, freq, pos)?,
+                None => writeln!(f, "word\t{}\t{}", word, freq)?,
+            }
+        }
+        write!(f, "{}", self.hmm)?;
+        if let Some(pos_hmm) = &self.pos_hmm {
+            for line in pos_hmm.to_string().lines() {
+                writeln!(f, "pos\t{}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+///
+/// Collects word frequencies and BMES tagging statistics from a segmented corpus,
+/// where each line is a sentence and words are separated by spaces (SIGHAN/PKU format).
+/// A POS-tagged corpus with words like "迈向/v" (People's Daily format) also gives part-of-speech statistics.
+///
+#[derive(Debug, Clone, Default)]
+pub struct Trainer {
+    word_freq: HashMap<String, u64>,
+    start: [u64; 4],
+    trans: [[u64; 4]; 4],
+    emit: [HashMap<String, u64>; 4],
+    sentences: usize,
+    word_tags: HashMap<String, HashMap<String, u64>>,
+    tag_start: HashMap<String, u64>,
+    tag_trans: HashMap<(String, String), u64>,
+}
+
+impl Trainer {
+    pub fn new() -> Trainer {
+        Trainer::default()
+    }
+
+    ///
+    /// Add a sentence given as its words
+    ///
+    pub fn add_sentence(&mut self, words: &[&str]) {
+        let mut prev: Option<usize> = None;
+        for word in words {
+            let graphemes: Vec<&str> = word.graphemes(true).collect();
+            if graphemes.is_empty() {
+                continue;
+            }
+            *self.word_freq.entry(word.to_string()).or_insert(0) += 1;
+            for (i, g) in graphemes.iter().enumerate() {
+                // indices into BMES
+                let state = match (i, graphemes.len()) {
+                    (_, 1) => 3,
+                    (0, _) => 0,
+                    (i, n) if i + 1 == n => 2,
+                    _ => 1,
+                };
+                match prev {
+                    Some(p) => self.trans[p][state] += 1,
+                    None => self.start[state] += 1,
+                }
+                *self.emit[state].entry(g.to_string()).or_insert(0) += 1;
+                prev = Some(state);
+            }
+        }
+        if prev.is_some() {
+            self.sentences += 1;
+        }
+    }
+
+    ///
+    /// Add a line of a segmented corpus, words separated by whitespace
+    ///
+    pub fn add_line(&mut self, line: &str) {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        self.add_sentence(&words);
+    }
+
+    ///
+    /// Add a sentence given as (word, part-of-speech tag) pairs
+    ///
+    pub fn add_tagged_sentence(&mut self, words: &[(&str, &str)]) {
+        let plain: Vec<&str> = words.iter().map(|(w, _)| *w).collect();
+        self.add_sentence(&plain);
+        let mut prev: Option<&str> = None;
+        for &(word, tag) in words.iter().filter(|(w, _)| !w.is_empty()) {
+            *self.word_tags.entry(word.to_string()).or_default().entry(tag.to_string()).or_insert(0) += 1;
+            match prev {
+                Some(p) => *self.tag_trans.entry((p.to_string(), tag.to_string())).or_insert(0) += 1,
+                None => *self.tag_start.entry(tag.to_string()).or_insert(0) += 1,
+            }
+            prev = Some(tag);
+        }
+    }
+
+    ///
+    /// Add a line of a POS-tagged corpus, "word/tag" items separated by whitespace. Items without a tag get "x".
+    ///
+    pub fn add_tagged_line(&mut self, line: &str) {
+        let words: Vec<(&str, &str)> = line
+            .split_whitespace()
+            .map(|item| match item.rsplit_once('/') {
+                Some((word, tag)) if !word.is_empty() && !tag.is_empty() => (word, tag),
+                _ => (item, "x"),
+            })
+            .collect();
+        self.add_tagged_sentence(&words);
+    }
+
+    ///
+    /// Add every line of a segmented corpus file
+    ///
+    pub fn add_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        for line in BufReader::new(File::open(path)?).lines() {
+            self.add_line(&line?);
+        }
+        Ok(())
+    }
+
+    ///
+    /// Add every line of a POS-tagged corpus file
+    ///
+    pub fn add_tagged_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        for line in BufReader::new(File::open(path)?).lines() {
+            self.add_tagged_line(&line?);
+        }
+        Ok(())
+    }
+
+    ///
+    /// Number of non-empty sentences seen so far
+    ///
+    pub fn sentences(&self) -> usize {
+        self.sentences
+    }
+
+    ///
+    /// Turn the counts into a model with natural log probabilities
+    ///
+    pub fn build(&self) -> SegmentationModel {
+        let mut dictionary = Dictionary::new();
+        for (word, &freq) in &self.word_freq {
+            // the most frequent tag, ties broken by name so that training is deterministic
+            let pos = self.word_tags.get(word).and_then(|tags| {
+                tags.iter().max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0))).map(|(tag, _)| tag.as_str())
+            });
+            dictionary.insert_entry(word, freq, pos);
+        }
+        let mut hmm = Hmm::new(&BMES);
+        let start_total: u64 = self.start.iter().sum();
+        for (s, &count) in self.start.iter().enumerate() {
+            if count > 0 {
+                hmm.set_start(BMES[s], log_ratio(count, start_total));
+            }
+        }
+        for (from, row) in self.trans.iter().enumerate() {
+            let total: u64 = row.iter().sum();
+            for (to, &count) in row.iter().enumerate() {
+                if count > 0 {
+                    hmm.set_trans(BMES[from], BMES[to], log_ratio(count, total));
+                }
+            }
+        }
+        for (s, emissions) in self.emit.iter().enumerate() {
+            let total: u64 = emissions.values().sum();
+            for (g, &count) in emissions {
+                hmm.set_emit(BMES[s], g, log_ratio(count, total));
+            }
+        }
+        SegmentationModel { dictionary, hmm, pos_hmm: self.build_pos_hmm() }
+    }
+
+    ///
+    /// Tag HMM over words. Words seen once estimate the emission of unknown words (`UNKNOWN_OBSERVATION`).
+    ///
+    fn build_pos_hmm(&self) -> Option<Hmm> {
+        if self.tag_start.is_empty() {
+            return None;
+        }
+        let mut hmm = Hmm::new(&[]);
+        let start_total: u64 = self.tag_start.values().sum();
+        for (tag, &count) in &self.tag_start {
+            hmm.set_start(tag, log_ratio(count, start_total));
+        }
+        let mut trans_totals: HashMap<&str, u64> = HashMap::new();
+        for ((from, _), &count) in &self.tag_trans {
+            *trans_totals.entry(from).or_insert(0) += count;
+        }
+        for ((from, to), &count) in &self.tag_trans {
+            hmm.set_trans(from, to, log_ratio(count, trans_totals[from.as_str()]));
+        }
+        let mut tag_totals: HashMap<&str, u64> = HashMap::new();
+        let mut hapax: HashMap<&str, u64> = HashMap::new();
+        for tags in self.word_tags.values() {
+            for (tag, &count) in tags {
+                *tag_totals.entry(tag).or_insert(0) += count;
+                if count == 1 {
+                    *hapax.entry(tag).or_insert(0) += 1;
+                }
+            }
+        }
+        for (word, tags) in &self.word_tags {
+            for (tag, &count) in tags {
+                hmm.set_emit(tag, word, log_ratio(count, tag_totals[tag.as_str()]));
+            }
+        }
+        for (tag, &total) in &tag_totals {
+            let unknown = hapax.get(tag).copied().unwrap_or(0) + 1;
+            hmm.set_emit(tag, UNKNOWN_OBSERVATION, log_ratio(unknown, total + 1));
+        }
+        Some(hmm)
+    }
+}
+
+fn log_ratio(count: u64, total: u64) -> f64 {
+    (count as f64 / total as f64).ln()
+}
+
+///
+/// Train a model from a segmented corpus file and write it to `model_path`
+///
+pub fn train_file<P: AsRef<Path>, Q: AsRef<Path>>(corpus_path: P, model_path: Q) -> Result<SegmentationModel> {
+    let mut trainer = Trainer::new();
+    trainer.add_file(corpus_path)?;
+    let model = trainer.build();
+    model.write_file(model_path)?;
+    Ok(model)
+}
@@ -2,13 +2,18 @@ extern crate word2vec;
 
 use word2vec::wordclusters::WordClusters;
 use word2vec::wordvectors::WordVector;
+use crate::error::{Error, Result};
+use log::warn;
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests{
+    #[allow(unused_imports)]
     use std::borrow::Borrow;
     use crate::native::word2vec::*;
 
-    
+    // disabled: needs a local word2vec model
+    #[allow(dead_code)]
     fn test_word2vec(){
             //this is an issue to fix
             let clusters=wv_clusters_create("D:\\UIBEResearch\\classes.txt");
@@ -16,7 +21,8 @@ mod tests{
             println!("index = {}",index);
     }
 
-    
+    // disabled: needs a local word2vec model
+    #[allow(dead_code)]
     fn test_open_wv_bin(){
         let wv_model=wv_get_model("D:\\UIBEResearch\\GoogleNews-vectors-negative300.bin\\GoogleNews-vectors-negative300.bin");
         let positive = vec!["woman", "king"];
@@ -25,7 +31,8 @@ mod tests{
         println!("cosine: {:?}", wv_cosine(&wv_model,"man", 10));
     }
 
-   
+    // disabled: needs a local word2vec model
+    #[allow(dead_code)]
     fn test_origin(){
 /*
     let model = word2vec::wordvectors::WordVector::load_from_binary(
@@ -49,9 +56,14 @@ mod tests{
 }
 
 pub fn wv_get_model(bin_path:&str)->WordVector{
-    let model = word2vec::wordvectors::WordVector::load_from_binary(
-        bin_path).expect("Unable to load word vector model");
-    return model
+    try_wv_get_model(bin_path).expect("Unable to load word vector model")
+}
+
+///
+/// Same as `wv_get_model`, but returns the IO or format error instead of panicking
+///
+pub fn try_wv_get_model(bin_path:&str)->Result<WordVector>{
+    Ok(WordVector::load_from_binary(bin_path)?)
 }
 
 
@@ -62,16 +74,14 @@ pub fn wv_get_model(bin_path:&str)->WordVector{
 ///
 ///
 pub fn wv_cosine(model:&WordVector,word:&str,n:usize)->Vec<(String,f32)>{
+    try_wv_cosine(model,word,n).unwrap_or_default()
+}
 
-    let ret=model.cosine(word,n);
-    match ret {
-        Some(r)=>{
-            r
-        },
-        None=>{
-            Vec::new()
-        },
-    }
+///
+/// Same as `wv_cosine`, but returns `Error::OutOfVocabulary` when the word is not in the model
+///
+pub fn try_wv_cosine(model:&WordVector,word:&str,n:usize)->Result<Vec<(String,f32)>>{
+    model.cosine(word,n).ok_or_else(|| Error::OutOfVocabulary(word.to_string()))
 }
 
 ///
@@ -80,17 +90,23 @@ pub fn wv_cosine(model:&WordVector,word:&str,n:usize)->Vec<(String,f32)>{
 ///     println!("{:?}", model.analogy(positive, negative, 10));
 ///
 pub fn wv_analogy(model:&WordVector, positive:Vec<&str>,negative:Vec<&str>,n:usize)->Vec<(String,f32)>{
-    let re=model.analogy(positive, negative, n);
-    // println!("{:?}",re );
-    match re{
-        Some(v)=>v,
-        None=>{
-            eprintln!("error");
+    match try_wv_analogy(model,positive,negative,n){
+        Ok(v)=>v,
+        Err(e)=>{
+            warn!("{}",e);
             Vec::new()
         }
     }
 }
 
+///
+/// Same as `wv_analogy`, but returns `Error::OutOfVocabulary` when one of the words is not in the model
+///
+pub fn try_wv_analogy(model:&WordVector, positive:Vec<&str>,negative:Vec<&str>,n:usize)->Result<Vec<(String,f32)>>{
+    let words=positive.iter().chain(negative.iter()).copied().collect::<Vec<&str>>().join(", ");
+    model.analogy(positive, negative, n).ok_or(Error::OutOfVocabulary(words))
+}
+
 ///
 ///
 /// let clusters = word2vec::wordclusters::WordClusters::load_from_file(
@@ -100,42 +116,57 @@ pub fn wv_analogy(model:&WordVector, positive:Vec<&str>,negative:Vec<&str>,n:usi
 ///
 ///
 pub fn wv_clusters_create(filepath:&str)->WordClusters{
-    let clusters = word2vec::wordclusters::WordClusters::load_from_file(
-        filepath).expect("Unable to load word clusters");
-    return clusters;
+    try_wv_clusters_create(filepath).expect("Unable to load word clusters")
+}
+
+///
+/// Same as `wv_clusters_create`, but returns the IO or format error instead of panicking
+///
+pub fn try_wv_clusters_create(filepath:&str)->Result<WordClusters>{
+    Ok(WordClusters::load_from_file(filepath)?)
 }
 
 ///
 ///  println!("{:?}", clusters.get_words_on_cluster(6));
 ///
 pub fn wv_get_cluster_from_clusters(clusters:WordClusters,word:&str)->i32{
-    match clusters.get_cluster(word){
-        Some(&v)=>{
+    match try_wv_get_cluster_from_clusters(&clusters,word){
+        Ok(v)=>{
             v
         },
-        None=>{
-            println!("error");
+        Err(e)=>{
+            warn!("{}",e);
             -1
         }
     }
 }
 
+///
+/// Same as `wv_get_cluster_from_clusters`, but returns `Error::OutOfVocabulary` instead of -1
+///
+pub fn try_wv_get_cluster_from_clusters(clusters:&WordClusters,word:&str)->Result<i32>{
+    clusters.get_cluster(word).copied().ok_or_else(|| Error::OutOfVocabulary(word.to_string()))
+}
+
 ///
 /// println!("{:?}", clusters.get_cluster("belarus"));
 ///
 pub fn wv_get_cluster_string(clusters:WordClusters,index:i32)->Vec<String>{
-    match clusters.get_words_on_cluster(index){
-        Some(v)=>{
-            v.clone()
+    match try_wv_get_cluster_string(&clusters,index){
+        Ok(v)=>{
+            v
         },
-        None=>{
-            println!("error");
+        Err(e)=>{
+            warn!("{}",e);
             Vec::new()
         }
     }
 }
 
+///
+/// Same as `wv_get_cluster_string`, but returns `Error::OutOfVocabulary` when no cluster has this index
+///
+pub fn try_wv_get_cluster_string(clusters:&WordClusters,index:i32)->Result<Vec<String>>{
+    clusters.get_words_on_cluster(index).cloned().ok_or_else(|| Error::OutOfVocabulary(format!("cluster {}",index)))
+}
 
-fn main(){
-
-}
\ No newline at end of file
@@ -1,24 +1,34 @@
 use std::collections::HashMap;
 use pyo3::prelude::*;
 use pyo3::types::IntoPyDict;
+use log::{debug, info, trace, warn};
+use crate::document::{Document, Entity, Sentence, Sentiment, Word};
+use crate::error::{Error, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
 
 ///
 /// This function expands a raw token into multiple syntactic words, which makes it easier to carry out Universal Dependencies analysis in some languages.
 /// https://stanfordnlp.github.io/stanza/mwt.html
 ///
-pub fn mwt_expand(text:&str,lang:&str)->Vec<Vec<HashMap<String,String>>>{
-    match _mwt_expand(text,lang){
-        Ok(t)=>{
-            // println!("{:?}",t);
-            t
-        },
+pub fn mwt_expand(text:&str,lang:&str)->Vec<Sentence>{
+    match try_mwt_expand(text,lang){
+        Ok(t)=>t,
         Err(e)=>{
-            println!("{:?}",e);
+            warn!("{}",e);
             Vec::new()
         }
     }
 }
 
+///
+/// Same as `mwt_expand`, but returns the error instead of an empty list
+///
+pub fn try_mwt_expand(text:&str,lang:&str)->Result<Vec<Sentence>>{
+    let t=_mwt_expand(text,lang)?;
+    Ok(t.iter().map(|rows| Sentence::from_maps(rows)).collect())
+}
+
 
 fn _mwt_expand(text:&str,lang:&str) -> PyResult<Vec<Vec<HashMap<String,String>>>> {
 
@@ -43,8 +53,6 @@ fn _mwt_expand(text:&str,lang:&str) -> PyResult<Vec<Vec<HashMap<String,String>>>
             "",
         )?.getattr("mwt_expand")?.call((),Some(kwargs.into_py_dict(py)))?.extract()?;
 
-        // println!("{:?}",results);
-
         Ok(results)
     })
 }
@@ -56,9 +64,9 @@ pub fn download_langs(list_lang:Vec<&str>){
     for lang in list_lang{
         let flag=download_lang(lang);
         if flag{
-            println!("Downloaded successfully!")
+            info!("Downloaded {} successfully!",lang)
         }else{
-            println!("Downloaded failed!")
+            warn!("Downloading {} failed!",lang)
         }
     }
 }
@@ -68,18 +76,23 @@ pub fn download_langs(list_lang:Vec<&str>){
 /// Download a necessary language model for the first time you use this toolkit!
 ///
 pub fn download_lang(lang:&str)->bool{
-    match _download_lang(lang){
-        Ok(t)=>{
-            // println!("{:?}",t);
-            t
-        },
+    match try_download_lang(lang){
+        Ok(())=>true,
         Err(e)=>{
-            println!("{:?}",e);
+            warn!("{}",e);
             false
         }
     }
 }
 
+///
+/// Same as `download_lang`, but returns the reason of a failed download
+///
+pub fn try_download_lang(lang:&str)->Result<()>{
+    _download_lang(lang)?;
+    Ok(())
+}
+
 fn _download_lang(lang:&str) -> PyResult<bool> {
 
     let py_tokenize=concat!(
@@ -104,8 +117,6 @@ fn _download_lang(lang:&str) -> PyResult<bool> {
             "",
         )?.getattr("download_lang")?.call((),Some(kwargs.into_py_dict(py)))?.extract()?;
 
-        // println!("{:?}",downloaded);
-
         Ok(downloaded)
     })
 }
@@ -118,18 +129,22 @@ fn _download_lang(lang:&str) -> PyResult<bool> {
 pub fn lang(list_str:Vec<&str>)->Vec<HashMap<String,String>>{
 
 
-    match _lang(list_str){
-        Ok(t)=>{
-            // println!("{:?}",t);
-            t
-        },
+    match try_lang(list_str){
+        Ok(t)=>t,
         Err(e)=>{
-            println!("{:?}",e);
+            warn!("{}",e);
             Vec::new()
         }
     }
 }
 
+///
+/// Same as `lang`, but returns the error instead of an empty list
+///
+pub fn try_lang(list_str:Vec<&str>)->Result<Vec<HashMap<String,String>>>{
+    Ok(_lang(list_str)?)
+}
+
 fn _lang(list_text:Vec<&str>) -> PyResult<Vec<HashMap<String,String>>> {
 
     let py_tokenize=concat!(
@@ -152,8 +167,6 @@ fn _lang(list_text:Vec<&str>) -> PyResult<Vec<HashMap<String,String>>> {
             "",
         )?.getattr("lang")?.call((),Some(kwargs.into_py_dict(py)))?.extract()?;
 
-        // println!("{:?}",tags);
-
         Ok(tags)
     })
 }
@@ -164,27 +177,31 @@ fn _lang(list_text:Vec<&str>) -> PyResult<Vec<HashMap<String,String>>> {
 /// The Part-of-Speech (POS) & morphological features tagging module labels words with their universal POS (UPOS) tags, treebank-specific POS (XPOS) tags, and universal morphological features (UFeats)
 /// https://stanfordnlp.github.io/stanza/pos.html
 ///
-pub fn pos(text:&str,lang:&str)->Vec<HashMap<String,String>>{
-
-    let tag_result;
-    if lang=="zh"{
-        tag_result=_pos_chinese(text,lang)
-    }else{
-        tag_result=_pos(text,lang)
-    }
-
-    match tag_result{
+pub fn pos(text:&str,lang:&str)->Vec<Word>{
+    match try_pos(text,lang){
         Ok(t)=>{
-            println!("{:?}",t);
+            debug!("{:?}",t);
             t
         },
         Err(e)=>{
-            println!("{:?}",e);
+            warn!("{}",e);
             Vec::new()
         }
     }
 }
 
+///
+/// Same as `pos`, but returns the error instead of an empty list
+///
+pub fn try_pos(text:&str,lang:&str)->Result<Vec<Word>>{
+    let tag_result=if lang=="zh"{
+        _pos_chinese(text,lang)
+    }else{
+        _pos(text,lang)
+    };
+    Ok(tag_result?.iter().map(Word::from_map).collect())
+}
+
 fn _pos(text:&str,lang:&str) -> PyResult<Vec<HashMap<String,String>>> {
 
     let py_tokenize=concat!(
@@ -208,7 +225,7 @@ fn _pos(text:&str,lang:&str) -> PyResult<Vec<HashMap<String,String>>> {
             "",
         )?.getattr("tag")?.call((),Some(kwargs.into_py_dict(py)))?.extract()?;
 
-        println!("{:?}",tags);
+        trace!("{:?}",tags);
 
         Ok(tags)
     })
@@ -237,7 +254,7 @@ fn _pos_chinese(text:&str,lang:&str) -> PyResult<Vec<HashMap<String,String>>> {
             "",
         )?.getattr("tag_chinese")?.call((),Some(kwargs.into_py_dict(py)))?.extract()?;
 
-        println!("{:?}",tags);
+        trace!("{:?}",tags);
 
         Ok(tags)
     })
@@ -248,19 +265,27 @@ fn _pos_chinese(text:&str,lang:&str) -> PyResult<Vec<HashMap<String,String>>> {
 /// The SentimentProcessor adds a label for sentiment to each Sentence. The existing models each support negative, neutral, and positive, represented by 0, 1, 2 respectively.
 /// https://stanfordnlp.github.io/stanza/sentiment.html
 ///
-pub fn sentiment(text:&str,lang:&str)->Vec<HashMap<String,String>>{
-    match _sentiment(text,lang){
+pub fn sentiment(text:&str,lang:&str)->Vec<Sentence>{
+    match try_sentiment(text,lang){
         Ok(t)=>{
-            println!("{:?}",t);
+            debug!("{:?}",t);
             t
         },
         Err(e)=>{
-            println!("{:?}",e);
+            warn!("{}",e);
             Vec::new()
         }
     }
 }
 
+///
+/// Same as `sentiment`, but returns the error instead of an empty list
+///
+pub fn try_sentiment(text:&str,lang:&str)->Result<Vec<Sentence>>{
+    let t=_sentiment(text,lang)?;
+    Ok(t.iter().map(sentence_from_sentiment_map).collect())
+}
+
 fn _sentiment(text:&str,lang:&str) -> PyResult<Vec<HashMap<String,String>>> {
 
     let py_tokenize=concat!(
@@ -284,7 +309,7 @@ fn _sentiment(text:&str,lang:&str) -> PyResult<Vec<HashMap<String,String>>> {
             "",
         )?.getattr("sentiment")?.call((),Some(kwargs.into_py_dict(py)))?.extract()?;
 
-        println!("{:?}",sentiments);
+        trace!("{:?}",sentiments);
 
         Ok(sentiments)
     })
@@ -294,19 +319,27 @@ fn _sentiment(text:&str,lang:&str) -> PyResult<Vec<HashMap<String,String>>> {
 /// It performs tokenization and sentence segmentation at the same time.
 /// https://stanfordnlp.github.io/stanza/tokenize.html
 ///
-pub fn tokenize(text:&str,lang:&str)->Vec<Vec<HashMap<String,String>>>{
-    match _tokenize(text,lang){
+pub fn tokenize(text:&str,lang:&str)->Vec<Sentence>{
+    match try_tokenize(text,lang){
         Ok(t)=>{
-            println!("{:?}",t);
+            debug!("{:?}",t);
             t
         },
         Err(e)=>{
-            println!("{:?}",e);
+            warn!("{}",e);
             Vec::new()
         }
     }
 }
 
+///
+/// Same as `tokenize`, but returns the error instead of an empty list
+///
+pub fn try_tokenize(text:&str,lang:&str)->Result<Vec<Sentence>>{
+    let t=_tokenize(text,lang)?;
+    Ok(t.iter().map(|rows| Sentence::from_maps(rows)).collect())
+}
+
 
 
 fn _tokenize(text:&str,lang:&str) -> PyResult<Vec<Vec<HashMap<String,String>>>> {
@@ -332,7 +365,7 @@ fn _tokenize(text:&str,lang:&str) -> PyResult<Vec<Vec<HashMap<String,String>>>>
             "",
         )?.getattr("tokenize")?.call((),Some(kwargs.into_py_dict(py)))?.extract()?;
 
-        println!("{:?}",list_result);
+        trace!("{:?}",list_result);
 
         Ok(list_result)
     })
@@ -342,23 +375,30 @@ fn _tokenize(text:&str,lang:&str) -> PyResult<Vec<Vec<HashMap<String,String>>>>
 /// You can perform tokenization without sentence segmentation, as long as the sentences are split by two continuous newlines (\n\n) in the raw text.
 ///
 pub fn tokenize_sentence(text:&str,lang:&str)->Vec<String>{
-    match _tokenize_sentence(text,lang){
+    match try_tokenize_sentence(text,lang){
         Ok(t)=>{
-            println!("{:?}",t);
+            debug!("{:?}",t);
             t
         },
         Err(e)=>{
-            println!("{:?}",e);
+            warn!("{}",e);
             Vec::new()
         }
     }
 }
 
+///
+/// Same as `tokenize_sentence`, but returns the error instead of an empty list
+///
+pub fn try_tokenize_sentence(text:&str,lang:&str)->Result<Vec<String>>{
+    Ok(_tokenize_sentence(text,lang)?)
+}
+
 fn _tokenize_sentence(text:&str,lang:&str) -> PyResult<Vec<String>> {
 
     let py_tokenize=concat!(
     "from nerkit.StanzaApi import StanzaWrapper\n",
-    "def tokenize(text,lang):\n",
+    "def tokenize_sentence(text,lang):\n",
     "\tsw=StanzaWrapper()\n",
     "\treturn sw.tokenize_sentence(text=text,lang=lang)\n"
     );
@@ -377,7 +417,7 @@ fn _tokenize_sentence(text:&str,lang:&str) -> PyResult<Vec<String>> {
             "",
         )?.getattr("tokenize_sentence")?.call((),Some(kwargs.into_py_dict(py)))?.extract()?;
 
-        println!("{:?}",list_result);
+        trace!("{:?}",list_result);
 
         Ok(list_result)
     })
@@ -387,19 +427,24 @@ fn _tokenize_sentence(text:&str,lang:&str) -> PyResult<Vec<String>> {
 /// The named entity recognition (NER) module recognizes mention spans of a particular entity type (e.g., Person or Organization) in the input sentence.
 /// https://stanfordnlp.github.io/stanza/ner.html
 ///
-pub fn ner(text:&str,lang:&str)->Vec<HashMap<String,String>>{
-    match _ner(text,lang){
-        Ok(t)=>{
-            //println!("{:?}",t);
-            t
-        },
+pub fn ner(text:&str,lang:&str)->Vec<Entity>{
+    match try_ner(text,lang){
+        Ok(t)=>t,
         Err(e)=>{
-            println!("{:?}",e);
+            warn!("{}",e);
             Vec::new()
         }
     }
 }
 
+///
+/// Same as `ner`, but returns the error instead of an empty list, so "no entities" and "Stanza not installed" can be told apart
+///
+pub fn try_ner(text:&str,lang:&str)->Result<Vec<Entity>>{
+    let t=_ner(text,lang)?;
+    Ok(t.iter().map(Entity::from_map).collect())
+}
+
 fn _ner(text:&str,lang:&str) -> PyResult<Vec<HashMap<String,String>>> {
 
     let py_ner=concat!(
@@ -423,8 +468,6 @@ fn _ner(text:&str,lang:&str) -> PyResult<Vec<HashMap<String,String>>> {
             "",
         )?.getattr("getNER")?.call((),Some(kwargs.into_py_dict(py)))?.extract()?;
 
-        // println!("{:?}",list_result);
-
         Ok(list_result)
     })
 }
@@ -433,27 +476,28 @@ fn _ner(text:&str,lang:&str) -> PyResult<Vec<HashMap<String,String>>> {
 /// The dependency parsing module builds a tree structure of words from the input sentence, which represents the syntactic dependency relations between words.
 /// https://stanfordnlp.github.io/stanza/depparse.html
 ///
-pub fn dependency_tree(text:&str,lang:&str)->Vec<Vec<HashMap<String,String>>>{
-
-    let result;
-    if lang=="zh"{
-        result=_dependency_tree_chinese(text,lang);
-    }else{
-        result=_dependency_tree(text,lang);
-    }
-
-    match result{
-        Ok(t)=>{
-            //println!("{:?}",t);
-            t
-        },
+pub fn dependency_tree(text:&str,lang:&str)->Vec<Sentence>{
+    match try_dependency_tree(text,lang){
+        Ok(t)=>t,
         Err(e)=>{
-            println!("{:?}",e);
+            warn!("{}",e);
             Vec::new()
         }
     }
 }
 
+///
+/// Same as `dependency_tree`, but returns the error instead of an empty list
+///
+pub fn try_dependency_tree(text:&str,lang:&str)->Result<Vec<Sentence>>{
+    let result=if lang=="zh"{
+        _dependency_tree_chinese(text,lang)
+    }else{
+        _dependency_tree(text,lang)
+    };
+    Ok(result?.iter().map(|rows| Sentence::from_maps(rows)).collect())
+}
+
 fn _dependency_tree_chinese(text:&str,lang:&str) -> PyResult<Vec<Vec<HashMap<String,String>>>> {
 
     let py_tokenize=concat!(
@@ -477,7 +521,7 @@ fn _dependency_tree_chinese(text:&str,lang:&str) -> PyResult<Vec<Vec<HashMap<Str
             "",
         )?.getattr("parse_dependency_chinese")?.call((),Some(kwargs.into_py_dict(py)))?.extract()?;
 
-        println!("{:?}",list_result);
+        trace!("{:?}",list_result);
 
         Ok(list_result)
     })
@@ -507,9 +551,574 @@ fn _dependency_tree(text:&str,lang:&str) -> PyResult<Vec<Vec<HashMap<String,Stri
             "",
         )?.getattr("parse_dependency")?.call((),Some(kwargs.into_py_dict(py)))?.extract()?;
 
-        println!("{:?}",list_result);
+        trace!("{:?}",list_result);
 
         Ok(list_result)
     })
 }
 
+
+///
+/// Build a sentence carrying only its text and sentiment label from a map returned by the sentiment processor
+///
+fn sentence_from_sentiment_map(row:&HashMap<String,String>)->Sentence{
+    let text=row.get("text").or_else(|| row.get("sentence")).cloned().unwrap_or_default();
+    let sentiment=row.get("sentiment")
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .and_then(Sentiment::from_index);
+    Sentence{text,sentiment,..Default::default()}
+}
+
+const PY_PIPELINE:&str=concat!(
+"import stanza\n",
+"_pipelines={}\n",
+"def _pipeline(lang,processors,tokenize_pretokenized,use_gpu,model_dir,offline):\n",
+"\tkey=(lang,processors,tokenize_pretokenized,use_gpu,model_dir,offline)\n",
+"\tif key not in _pipelines:\n",
+"\t\tkwargs={'lang':lang,'processors':processors,'tokenize_pretokenized':tokenize_pretokenized,'use_gpu':use_gpu,'verbose':False}\n",
+"\t\tif model_dir:\n",
+"\t\t\tkwargs['dir']=model_dir\n",
+"\t\tif offline:\n",
+"\t\t\tkwargs['download_method']=None\n",
+"\t\t_pipelines[key]=stanza.Pipeline(**kwargs)\n",
+"\treturn _pipelines[key]\n",
+"def download(lang,model_dir,processors):\n",
+"\tkwargs={'verbose':False}\n",
+"\tif model_dir:\n",
+"\t\tkwargs['model_dir']=model_dir\n",
+"\tif processors:\n",
+"\t\tkwargs['processors']=processors\n",
+"\tstanza.download(lang,**kwargs)\n",
+"def clear():\n",
+"\t_pipelines.clear()\n",
+"def loaded():\n",
+"\treturn [k[0]+':'+k[1] for k in _pipelines]\n",
+"def _row(d):\n",
+"\treturn {k:str(v) for k,v in d.items() if v is not None}\n",
+"def _sentence(sent):\n",
+"\tmeta={'text':sent.text}\n",
+"\tif getattr(sent,'sentiment',None) is not None:\n",
+"\t\tmeta['sentiment']=str(sent.sentiment)\n",
+"\trows=[_row(d) for d in sent.to_dict()]\n",
+"\tents=[_row(e.to_dict()) for e in getattr(sent,'ents',[])]\n",
+"\treturn (meta,rows,ents)\n",
+"def annotate(text,config):\n",
+"\tnlp=_pipeline(*config)\n",
+"\tdoc=nlp(text)\n",
+"\treturn [_sentence(s) for s in doc.sentences]\n",
+"def annotate_batch(texts,config):\n",
+"\tnlp=_pipeline(*config)\n",
+"\tdocs=nlp([stanza.Document([],text=t) for t in texts])\n",
+"\treturn [[_sentence(s) for s in d.sentences] for d in docs]\n"
+);
+
+type PySentence=(HashMap<String,String>,Vec<HashMap<String,String>>,Vec<HashMap<String,String>>);
+type PyPipelineConfig=(String,String,bool,bool,Option<String>,bool);
+
+///
+/// The processors a Stanza pipeline can run, in the order Stanza expects them.
+/// https://stanfordnlp.github.io/stanza/pipeline.html#processors
+///
+pub const PROCESSORS:[&str;7]=["tokenize","mwt","pos","lemma","depparse","ner","sentiment"];
+
+///
+/// A Stanza pipeline that runs the selected processors once and returns a single annotated `Document`
+/// carrying all layers (tokens, multi-word expansion, POS, lemmas, dependencies, entities and sentiment).
+///
+/// let doc=Pipeline::new("en").processors(&["tokenize","pos","lemma","depparse","ner"]).annotate("I like Beijing!");
+///
+#[derive(Debug,Clone,PartialEq)]
+pub struct Pipeline{
+    lang:String,
+    processors:Vec<String>,
+    tokenize_pretokenized:bool,
+    use_gpu:bool,
+    model_dir:Option<PathBuf>,
+    offline:bool,
+}
+
+impl Pipeline{
+    ///
+    /// Create a pipeline for a language code such as "en" or "zh" with only the tokenizer enabled
+    ///
+    pub fn new(lang:&str)->Pipeline{
+        Pipeline{
+            lang:lang.to_string(),
+            processors:vec!["tokenize".to_string()],
+            tokenize_pretokenized:false,
+            use_gpu:true,
+            model_dir:None,
+            offline:false,
+        }
+    }
+
+    ///
+    /// Add a processor, e.g. "pos" or "ner". The tokenizer is always included.
+    ///
+    pub fn processor(mut self,name:&str)->Pipeline{
+        let name=name.trim();
+        if !name.is_empty() && !self.processors.iter().any(|p| p==name){
+            self.processors.push(name.to_string());
+        }
+        self
+    }
+
+    ///
+    /// Add several processors at once, e.g. &["mwt","pos","lemma","depparse"]
+    ///
+    pub fn processors(self,names:&[&str])->Pipeline{
+        names.iter().fold(self,|p,name| p.processor(name))
+    }
+
+    ///
+    /// Treat the input as already tokenized: tokens separated by spaces, sentences by newlines
+    ///
+    pub fn tokenize_pretokenized(mut self,flag:bool)->Pipeline{
+        self.tokenize_pretokenized=flag;
+        self
+    }
+
+    pub fn use_gpu(mut self,flag:bool)->Pipeline{
+        self.use_gpu=flag;
+        self
+    }
+
+    ///
+    /// Load models from this directory instead of `resources_dir()`
+    ///
+    pub fn model_dir<P:AsRef<Path>>(mut self,dir:P)->Pipeline{
+        self.model_dir=Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    ///
+    /// Never download anything: fail with `Error::ModelNotDownloaded` before calling Stanza
+    /// when a model of the pipeline is missing from the model directory
+    ///
+    pub fn offline(mut self,flag:bool)->Pipeline{
+        self.offline=flag;
+        self
+    }
+
+    pub fn lang(&self)->&str{
+        &self.lang
+    }
+
+    ///
+    /// The directory the models are loaded from
+    ///
+    pub fn resolved_model_dir(&self)->PathBuf{
+        self.model_dir.clone().unwrap_or_else(resources_dir)
+    }
+
+    ///
+    /// Check that every processor of the pipeline is installed, without starting Python
+    ///
+    pub fn check_models(&self)->Result<()>{
+        let dir=self.resolved_model_dir();
+        let processors:Vec<&str>=self.processors.iter().map(|p| p.as_str()).collect();
+        let missing=missing_processors(&dir,&self.lang,&processors);
+        if missing.is_empty(){
+            Ok(())
+        }else{
+            Err(Error::ModelNotDownloaded(format!("{}: {} (in {})",self.lang,missing.join(", "),dir.display())))
+        }
+    }
+
+    ///
+    /// Download the models of this pipeline into its model directory
+    ///
+    pub fn download(&self)->Result<()>{
+        StanzaSession::new()?.download(self)
+    }
+
+    ///
+    /// The pipeline settings passed to the Python helper. An offline pipeline first checks that its models are installed.
+    ///
+    fn py_config(&self)->Result<PyPipelineConfig>{
+        if self.offline{
+            self.check_models()?;
+        }
+        let model_dir=self.model_dir.as_ref().map(|d| d.to_string_lossy().to_string());
+        Ok((self.lang.clone(),self.processor_list(),self.tokenize_pretokenized,self.use_gpu,model_dir,self.offline))
+    }
+
+    ///
+    /// The processor list passed to Stanza, e.g. "tokenize,mwt,pos", ordered as in `PROCESSORS`
+    ///
+    pub fn processor_list(&self)->String{
+        let mut names:Vec<&str>=PROCESSORS.iter().copied()
+            .filter(|p| self.processors.iter().any(|q| q==p)).collect();
+        for p in &self.processors{
+            if !PROCESSORS.contains(&p.as_str()){
+                names.push(p);
+            }
+        }
+        names.join(",")
+    }
+
+    ///
+    /// Run the pipeline once over the text and collect every annotation layer into a `Document`.
+    /// This loads the Stanza models on every call; use a `StanzaSession` to keep them loaded.
+    ///
+    pub fn annotate(&self,text:&str)->Result<Document>{
+        let config=self.py_config()?;
+        StanzaSession::new()?.annotate_with(config,text)
+    }
+
+    ///
+    /// Run the pipeline over many texts using Stanza's bulk processing. See `StanzaSession::annotate_batch`.
+    ///
+    pub fn annotate_batch(&self,texts:&[&str],chunk_size:Option<usize>)->Result<Vec<Document>>{
+        let config=self.py_config()?;
+        StanzaSession::new()?.annotate_batch_with(config,texts,chunk_size)
+    }
+}
+
+///
+/// A long-lived handle to the Python helper module and the Stanza pipelines it has loaded.
+/// Pipelines are cached per language and processor list, so repeated calls only pay inference cost.
+///
+/// let session=StanzaSession::new()?;
+/// for text in texts { let entities=session.ner(text,"en")?; }
+///
+#[derive(Debug,Clone)]
+pub struct StanzaSession{
+    module:Py<PyModule>,
+}
+
+impl StanzaSession{
+    ///
+    /// Compile the helper module once. Stanza models are loaded lazily on first use of each pipeline.
+    ///
+    pub fn new()->Result<StanzaSession>{
+        Python::with_gil(|py| {
+            let module:Py<PyModule>=PyModule::from_code(py,PY_PIPELINE,"rsnltk_stanza.py","rsnltk_stanza")?.into();
+            Ok(StanzaSession{module})
+        })
+    }
+
+    ///
+    /// Run a pipeline over the text, reusing the loaded Stanza pipeline when the same configuration was used before
+    ///
+    pub fn annotate(&self,pipeline:&Pipeline,text:&str)->Result<Document>{
+        self.annotate_with(pipeline.py_config()?,text)
+    }
+
+    fn annotate_with(&self,config:PyPipelineConfig,text:&str)->Result<Document>{
+        Python::with_gil(|py| {
+            let sentences:Vec<PySentence>=self.module.as_ref(py)
+                .getattr("annotate")?
+                .call1((text,config))?
+                .extract()?;
+            Ok(document_from_py(text,sentences))
+        })
+    }
+
+    ///
+    /// Run a pipeline over many texts, sending each chunk of `chunk_size` texts to Stanza in one call
+    /// (all texts at once when `None`). The returned documents are in the same order as `texts`.
+    ///
+    pub fn annotate_batch(&self,pipeline:&Pipeline,texts:&[&str],chunk_size:Option<usize>)->Result<Vec<Document>>{
+        self.annotate_batch_with(pipeline.py_config()?,texts,chunk_size)
+    }
+
+    fn annotate_batch_with(&self,config:PyPipelineConfig,texts:&[&str],chunk_size:Option<usize>)->Result<Vec<Document>>{
+        let chunk_size=match chunk_size{
+            Some(n) if n>0=>n,
+            _=>texts.len().max(1),
+        };
+        let mut documents=Vec::with_capacity(texts.len());
+        for chunk in texts.chunks(chunk_size){
+            let results:Vec<Vec<PySentence>>=Python::with_gil(|py| {
+                self.module.as_ref(py)
+                    .getattr("annotate_batch")?
+                    .call1((chunk.to_vec(),config.clone()))?
+                    .extract()
+            })?;
+            for (text,sentences) in chunk.iter().zip(results){
+                documents.push(document_from_py(text,sentences));
+            }
+        }
+        Ok(documents)
+    }
+
+    ///
+    /// Download the models of a pipeline into its model directory
+    ///
+    pub fn download(&self,pipeline:&Pipeline)->Result<()>{
+        let model_dir=pipeline.model_dir.as_ref().map(|d| d.to_string_lossy().to_string());
+        Python::with_gil(|py| {
+            self.module.as_ref(py).getattr("download")?.call1((&pipeline.lang,model_dir,pipeline.processor_list()))?;
+            Ok(())
+        })
+    }
+
+    ///
+    /// Drop all cached Stanza pipelines and free their models
+    ///
+    pub fn clear(&self)->Result<()>{
+        Python::with_gil(|py| {
+            self.module.as_ref(py).getattr("clear")?.call0()?;
+            Ok(())
+        })
+    }
+
+    ///
+    /// The currently loaded pipelines as "lang:processors" strings
+    ///
+    pub fn loaded_pipelines(&self)->Result<Vec<String>>{
+        Python::with_gil(|py| {
+            Ok(self.module.as_ref(py).getattr("loaded")?.call0()?.extract()?)
+        })
+    }
+
+    ///
+    /// Split text into sentences made of tokens
+    ///
+    pub fn tokenize(&self,text:&str,lang:&str)->Result<Vec<Sentence>>{
+        Ok(self.annotate(&Pipeline::new(lang),text)?.sentences)
+    }
+
+    ///
+    /// Split text into sentences and return the sentence texts
+    ///
+    pub fn tokenize_sentence(&self,text:&str,lang:&str)->Result<Vec<String>>{
+        Ok(self.tokenize(text,lang)?.into_iter().map(|s| s.text).collect())
+    }
+
+    ///
+    /// Tokenize and expand multi-word tokens into syntactic words
+    ///
+    pub fn mwt_expand(&self,text:&str,lang:&str)->Result<Vec<Sentence>>{
+        Ok(self.annotate(&Pipeline::new(lang).processor("mwt"),text)?.sentences)
+    }
+
+    ///
+    /// Tag all words with UPOS, XPOS and morphological features
+    ///
+    pub fn pos(&self,text:&str,lang:&str)->Result<Vec<Word>>{
+        let doc=self.annotate(&Pipeline::new(lang).processors(&["mwt","pos"]),text)?;
+        Ok(doc.words().cloned().collect())
+    }
+
+    ///
+    /// Recognize named entity mentions
+    ///
+    pub fn ner(&self,text:&str,lang:&str)->Result<Vec<Entity>>{
+        let doc=self.annotate(&Pipeline::new(lang).processors(&["mwt","ner"]),text)?;
+        Ok(doc.entities().cloned().collect())
+    }
+
+    ///
+    /// Label each sentence as negative, neutral or positive
+    ///
+    pub fn sentiment(&self,text:&str,lang:&str)->Result<Vec<Sentence>>{
+        Ok(self.annotate(&Pipeline::new(lang).processor("sentiment"),text)?.sentences)
+    }
+
+    ///
+    /// Parse the dependency tree of each sentence
+    ///
+    pub fn dependency_tree(&self,text:&str,lang:&str)->Result<Vec<Sentence>>{
+        let pipeline=Pipeline::new(lang).processors(&["mwt","pos","lemma","depparse"]);
+        Ok(self.annotate(&pipeline,text)?.sentences)
+    }
+
+    ///
+    /// Batch version of `tokenize`, one list of sentences per input text
+    ///
+    pub fn tokenize_batch(&self,texts:&[&str],lang:&str,chunk_size:Option<usize>)->Result<Vec<Vec<Sentence>>>{
+        let docs=self.annotate_batch(&Pipeline::new(lang),texts,chunk_size)?;
+        Ok(docs.into_iter().map(|d| d.sentences).collect())
+    }
+
+    ///
+    /// Batch version of `pos`, one list of words per input text
+    ///
+    pub fn pos_batch(&self,texts:&[&str],lang:&str,chunk_size:Option<usize>)->Result<Vec<Vec<Word>>>{
+        let docs=self.annotate_batch(&Pipeline::new(lang).processors(&["mwt","pos"]),texts,chunk_size)?;
+        Ok(docs.iter().map(|d| d.words().cloned().collect()).collect())
+    }
+
+    ///
+    /// Batch version of `ner`, one list of entities per input text
+    ///
+    pub fn ner_batch(&self,texts:&[&str],lang:&str,chunk_size:Option<usize>)->Result<Vec<Vec<Entity>>>{
+        let docs=self.annotate_batch(&Pipeline::new(lang).processors(&["mwt","ner"]),texts,chunk_size)?;
+        Ok(docs.iter().map(|d| d.entities().cloned().collect()).collect())
+    }
+
+    ///
+    /// Batch version of `sentiment`, one list of sentences per input text
+    ///
+    pub fn sentiment_batch(&self,texts:&[&str],lang:&str,chunk_size:Option<usize>)->Result<Vec<Vec<Sentence>>>{
+        let docs=self.annotate_batch(&Pipeline::new(lang).processor("sentiment"),texts,chunk_size)?;
+        Ok(docs.into_iter().map(|d| d.sentences).collect())
+    }
+
+    ///
+    /// Batch version of `dependency_tree`, one list of parsed sentences per input text
+    ///
+    pub fn dependency_tree_batch(&self,texts:&[&str],lang:&str,chunk_size:Option<usize>)->Result<Vec<Vec<Sentence>>>{
+        let pipeline=Pipeline::new(lang).processors(&["mwt","pos","lemma","depparse"]);
+        let docs=self.annotate_batch(&pipeline,texts,chunk_size)?;
+        Ok(docs.into_iter().map(|d| d.sentences).collect())
+    }
+}
+
+fn document_from_py(text:&str,sentences:Vec<PySentence>)->Document{
+    let sentences=sentences.into_iter().map(|(meta,rows,ents)|{
+        let mut sentence=Sentence::from_maps(&rows);
+        if let Some(t)=meta.get("text"){
+            sentence.text=t.clone();
+        }
+        sentence.sentiment=meta.get("sentiment")
+            .and_then(|v| v.trim().parse::<usize>().ok())
+            .and_then(Sentiment::from_index);
+        sentence.entities=ents.iter().map(Entity::from_map).collect();
+        sentence
+    }).collect();
+    Document{text:text.to_string(),sentences}
+}
+
+///
+/// Tokenize many documents in bulk. Results are aligned with `texts`; unlike `tokenize`,
+/// errors are returned rather than swallowed so a failure never shifts the alignment.
+///
+pub fn tokenize_batch(texts:&[&str],lang:&str,chunk_size:Option<usize>)->Result<Vec<Vec<Sentence>>>{
+    StanzaSession::new()?.tokenize_batch(texts,lang,chunk_size)
+}
+
+///
+/// Tag many documents in bulk, one list of words per input text
+///
+pub fn pos_batch(texts:&[&str],lang:&str,chunk_size:Option<usize>)->Result<Vec<Vec<Word>>>{
+    StanzaSession::new()?.pos_batch(texts,lang,chunk_size)
+}
+
+///
+/// Recognize named entities in many documents in bulk, one list of entities per input text
+///
+pub fn ner_batch(texts:&[&str],lang:&str,chunk_size:Option<usize>)->Result<Vec<Vec<Entity>>>{
+    StanzaSession::new()?.ner_batch(texts,lang,chunk_size)
+}
+
+///
+/// Label the sentiment of many documents in bulk, one list of sentences per input text
+///
+pub fn sentiment_batch(texts:&[&str],lang:&str,chunk_size:Option<usize>)->Result<Vec<Vec<Sentence>>>{
+    StanzaSession::new()?.sentiment_batch(texts,lang,chunk_size)
+}
+
+///
+/// Parse many documents in bulk, one list of parsed sentences per input text
+///
+pub fn dependency_tree_batch(texts:&[&str],lang:&str,chunk_size:Option<usize>)->Result<Vec<Vec<Sentence>>>{
+    StanzaSession::new()?.dependency_tree_batch(texts,lang,chunk_size)
+}
+
+/// Directories next to the processor models that hold shared resources rather than a processor
+const SUPPORT_DIRS:[&str;3]=["pretrain","forward_charlm","backward_charlm"];
+
+///
+/// The Stanza model directory: `STANZA_RESOURCES_DIR` if set, otherwise `~/stanza_resources`
+///
+pub fn resources_dir()->PathBuf{
+    if let Some(dir)=std::env::var_os("STANZA_RESOURCES_DIR"){
+        return PathBuf::from(dir);
+    }
+    let home=std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")).unwrap_or_default();
+    PathBuf::from(home).join("stanza_resources")
+}
+
+///
+/// Languages with at least one processor model installed in `dir`, e.g. ["en","zh"]
+///
+pub fn installed_languages(dir:&Path)->Result<Vec<String>>{
+    let mut langs=Vec::new();
+    for entry in fs::read_dir(dir)?{
+        let entry=entry?;
+        if !entry.file_type()?.is_dir(){
+            continue;
+        }
+        let lang=entry.file_name().to_string_lossy().to_string();
+        if !lang_processors(&entry.path())?.is_empty(){
+            langs.push(lang);
+        }
+    }
+    langs.sort();
+    Ok(langs)
+}
+
+///
+/// Processors with a model installed for a language in `dir`, e.g. ["depparse","lemma","pos","tokenize"].
+/// The language is looked up under its resources.json alias, e.g. "zh" in "zh-hans".
+///
+pub fn installed_processors(dir:&Path,lang:&str)->Result<Vec<String>>{
+    let resources=read_resources(dir);
+    lang_processors(&dir.join(resolve_lang(resources.as_ref(),lang)))
+}
+
+fn lang_processors(lang_dir:&Path)->Result<Vec<String>>{
+    if !lang_dir.is_dir(){
+        return Ok(Vec::new());
+    }
+    let mut processors=Vec::new();
+    for entry in fs::read_dir(lang_dir)?{
+        let entry=entry?;
+        let name=entry.file_name().to_string_lossy().to_string();
+        if entry.file_type()?.is_dir() && !SUPPORT_DIRS.contains(&name.as_str()) && has_files(&entry.path()){
+            processors.push(name);
+        }
+    }
+    processors.sort();
+    Ok(processors)
+}
+
+///
+/// The processors of the list that are not installed for a language in `dir`.
+/// "mwt" is not reported when resources.json says the language has no such model (e.g. "zh"), as Stanza skips it then.
+///
+pub fn missing_processors(dir:&Path,lang:&str,processors:&[&str])->Vec<String>{
+    let resources=read_resources(dir);
+    let lang=resolve_lang(resources.as_ref(),lang);
+    let installed=lang_processors(&dir.join(&lang)).unwrap_or_default();
+    // None when resources.json cannot be read or does not know the language
+    let has_mwt_package=resources.as_ref().and_then(|r| r.get(&lang)).map(|entry| entry.get("mwt").is_some());
+    let no_mwt_package=has_mwt_package==Some(false);
+    processors.iter()
+        .filter(|p| !installed.iter().any(|i| i==*p) && (**p!="mwt" || !no_mwt_package))
+        .map(|p| p.to_string())
+        .collect()
+}
+
+///
+/// The resources.json index of the models in `dir`, `None` when it cannot be read
+///
+fn read_resources(dir:&Path)->Option<serde_json::Value>{
+    let text=fs::read_to_string(dir.join("resources.json")).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+///
+/// The language code the models are stored under: its resources.json alias (e.g. "zh" to "zh-hans") if any
+///
+fn resolve_lang(resources:Option<&serde_json::Value>,lang:&str)->String{
+    resources.and_then(|r| r.get(lang))
+        .and_then(|entry| entry.get("alias"))
+        .and_then(|alias| alias.as_str())
+        .unwrap_or(lang)
+        .to_string()
+}
+
+///
+/// Whether all the processors are installed for a language in `dir`
+///
+pub fn is_model_installed(dir:&Path,lang:&str,processors:&[&str])->bool{
+    missing_processors(dir,lang,processors).is_empty()
+}
+
+fn has_files(dir:&Path)->bool{
+    fs::read_dir(dir).map(|mut entries| entries.any(|e| e.map(|e| e.path().is_file()).unwrap_or(false))).unwrap_or(false)
+}
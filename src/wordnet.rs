@@ -1,18 +1,27 @@
 use std::collections::HashMap;
 use pyo3::prelude::*;
+use log::warn;
+use crate::error::Result;
 ///
 /// Estimate the similarity between twn synsets based on WordNet (pip install semantic-kit) required
 ///
 pub fn wordnet_similarity(s1:&str,s2:&str)->HashMap<String,f32>{
-    match _wordnet_similarity(s1,s2){
+    match try_wordnet_similarity(s1,s2){
         Ok(sims)=>sims,
         Err(e)=>{
-            eprintln!("{:?}",e);
+            warn!("{}",e);
             HashMap::new()
         }
     }
 }
 
+///
+/// Same as `wordnet_similarity`, but returns the error, e.g. `Error::ModuleImport` when semantic-kit is not installed
+///
+pub fn try_wordnet_similarity(s1:&str,s2:&str)->Result<HashMap<String,f32>>{
+    Ok(_wordnet_similarity(s1,s2)?)
+}
+
 fn _wordnet_similarity(s1:&str,s2:&str)-> PyResult<HashMap<String,f32>> {
     Python::with_gil(|py| {
         let semantickit = PyModule::import(py, "semantickit.similarity.wordnet_similarity")?;
@@ -1,6 +1,8 @@
 #[cfg(test)]
 mod tests {
 
+    #[cfg(feature = "python")]
+    #[allow(unused_imports)]
     use rsnltk::wordnet::wordnet_similarity;
     use rsnltk::api::natural::*;
     use rsnltk::api::whatlang::*;
@@ -2,6 +2,7 @@
 mod tests {
     use rsnltk::native::token::*;
     use rsnltk::native::summarizer::*;
+    use rsnltk::native::keywords::*;
     # [test]
     fn test_get_token_list(){
         let s="Hello, Rust. How are you?";
@@ -19,4 +20,52 @@ mod tests {
         println!("{}",summarized_text);
     }
 
-}
\ No newline at end of file
+    # [test]
+    fn test_summarize_long_document(){
+        // 600 sentences drawn from a small vocabulary; see benches/summarizer.rs for timings
+        let vocabulary=["market","price","rust","memory","summer","river","court","vote","energy","school","garden","engine","coffee","music","science","travel"];
+        let text:String=(0..600).map(|i| {
+            let words:Vec<&str>=(0..12).map(|k| vocabulary[(i*7+k*k*3+i/5)%vocabulary.len()]).collect();
+            format!("Sentence {} says {}. ",i,words.join(" "))
+        }).collect();
+        let summary=Summarizer::new().summarize_ranked(&text,SummaryLength::Sentences(10));
+        assert_eq!(summary.len(),10);
+    }
+
+    # [test]
+    fn test_summarize_chinese(){
+        use rsnltk::native::dictionary::Dictionary;
+        use rsnltk::native::segmentation::Segmenter;
+        let text="北京是中国的首都。北京有很多名胜古迹，故宫是北京最有名的名胜古迹。今天天气很好。上海是中国最大的城市。";
+        let dict=Dictionary::from_words(vec!["北京","中国","首都","名胜古迹","故宫","有名","今天","天气","上海","城市","最大"]);
+        let summarizer=Summarizer::new().splitter(CjkSentenceSplitter).tokenizer(Segmenter::new(dict)).stop_words(vec!["的","是"]);
+        let summary=summarizer.summarize(text,2);
+        assert!(summary.contains("故宫是北京最有名的名胜古迹"));
+        assert!(!summary.contains("天气"));
+    }
+
+    # [test]
+    fn test_keywords(){
+        use rsnltk::native::dictionary::Dictionary;
+        use rsnltk::native::segmentation::Segmenter;
+        let text="Rust is fast. Rust is memory safe. Memory safety without garbage collection makes Rust popular.";
+        let config=KeywordConfig{top_k:2,..Default::default()}.stop_words(vec!["is","without","makes"]);
+        let words=split_words(text);
+        let mut keywords:Vec<String>=text_rank(&words,&config).into_iter().map(|k| k.word).collect();
+        keywords.sort();
+        assert_eq!(keywords,vec!["memory","rust"]);
+        let idf=IdfTable::from_documents(&[words.clone(),split_words("Rust is a language"),split_words("Rust has crates")]);
+        let keywords=tf_idf(&words,&idf,&config);
+        assert_eq!(keywords[0].word,"memory");
+
+        let mut dict=Dictionary::new();
+        for (word,pos) in [("我","r"),("喜欢","v"),("北京","ns"),("的","uj"),("故宫","ns"),("和","c"),("长城","ns")]{
+            dict.insert_entry(word,10,Some(pos));
+        }
+        let segmenter=Segmenter::new(dict);
+        let tagged=segmenter.tag("我喜欢北京的故宫和北京的长城");
+        let config=KeywordConfig{top_k:3,allowed_pos:vec!["ns".to_string()],..Default::default()};
+        let keywords=text_rank_tagged(&tagged,&config);
+        assert_eq!(keywords.iter().map(|k| k.word.as_str()).collect::<Vec<&str>>(),vec!["北京","故宫","长城"]);
+    }
+}
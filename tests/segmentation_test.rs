@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests{
     use rsnltk::native::segmentation::*;
+    use rsnltk::native::dictionary::Dictionary;
     extern crate  unicode_segmentation;
     use unicode_segmentation::UnicodeSegmentation;
     # [test]
@@ -15,11 +16,12 @@ mod tests{
         let sss=String::from("我喜欢吃苹果，也爱打羽毛球");
         let sentence = sss.graphemes(true).collect::<Vec<&str>>();
 
-        let dict=vec!["我".to_string(),"喜欢".to_string(),"苹果".to_string(),"羽毛球".to_string(),"爱".to_string()];
+        let dict=Dictionary::from_words(vec!["我","喜欢","苹果","羽毛球","爱"]);
 
-        let results=bmm(sentence,dict);
+        let results=bmm(&sentence,&dict);
 
         println!("{:?}",results);
+        assert_eq!(results,vec!["我","喜欢","吃","苹果","，","也","爱","打","羽毛球"]);
     }
 
     # [test]
@@ -27,9 +29,9 @@ mod tests{
         let sss=String::from("我喜欢吃苹果，也爱打羽毛球");
         let sentence = sss.graphemes(true).collect::<Vec<&str>>();
 
-        let dict=vec!["我".to_string(),"喜欢".to_string(),"苹果".to_string(),"羽毛球".to_string(),"爱".to_string()];
+        let dict=Dictionary::from_words(vec!["我","喜欢","苹果","羽毛球","爱"]);
 
-        let result=fmm(sentence,dict);
+        let result=fmm(&sentence,&dict);
 
         println!("{:?}",result)
 
@@ -40,13 +42,258 @@ mod tests{
         let sss=String::from("我喜欢吃苹果，也爱打羽毛球");
         let sentence = sss.graphemes(true).collect::<Vec<&str>>();
 
-        let dict=vec!["我".to_string(),"喜欢".to_string(),"苹果".to_string(),"羽毛球".to_string(),"爱".to_string()];
+        let dict=Dictionary::from_words(vec!["我","喜欢","苹果","羽毛球","爱"]);
 
-        let result=bimm(sentence,dict);
+        let result=bimm(&sentence,&dict);
 
         println!("{:?}",result)
     }
 
+    # [test]
+    fn test_bimm_prefers_higher_score(){
+        // fmm gives 研究生/命/的/起源 with two single-grapheme words, bmm gives 研究/生命/的/起源 with one,
+        // so bmm scores higher and bimm has to pick it
+        let dict=Dictionary::from_words(vec!["研究","研究生","生命","命","的","起源"]);
+        let sentence="研究生命的起源".graphemes(true).collect::<Vec<&str>>();
+        assert_eq!(bmm(&sentence,&dict),vec!["研究","生命","的","起源"]);
+        assert_eq!(bimm(&sentence,&dict),vec!["研究","生命","的","起源"]);
+    }
+
+    # [test]
+    fn test_shared_dictionary(){
+        // load once, segment many sentences
+        let dict=Dictionary::from_words(vec!["太空","太空总署","美国","探险","外太空","秘密"]);
+        for method in ["fmm","bmm","bimm"]{
+            let words=segment("美国太空总署希望在深海探险",&dict,method);
+            assert_eq!(&words[..3],&["美国","太空总署","希"]);
+        }
+        assert_eq!(segment("外太空的秘密",&dict,"bmm"),vec!["外太空","的","秘密"]);
+    }
+
+    # [test]
+    fn test_max_word_len(){
+        // entries longer than four graphemes are matched when the window follows the dictionary
+        let dict=Dictionary::from_words(vec!["中华人民共和国","中华","人民","共和国"]);
+        assert_eq!(dict.max_word_len(),7);
+        let sentence="中华人民共和国成立了";
+        let config=SegmenterConfig{method:SegmentMethod::Fmm,..Default::default()};
+        assert_eq!(segment_with_config(sentence,&dict,&config)[0],"中华人民共和国");
+        let config=SegmenterConfig{method:SegmentMethod::Bmm,max_word_len:Some(4),..Default::default()};
+        assert_eq!(&segment_with_config(sentence,&dict,&config)[..3],&["中华","人民","共和国"]);
+        // the window counts atomic units: "Rust语言" is three units, not six graphemes
+        let dict=Dictionary::from_words(vec!["Rust语言"]);
+        let config=SegmenterConfig{method:SegmentMethod::Fmm,max_word_len:Some(3),..Default::default()};
+        assert_eq!(segment_with_config("学Rust语言",&dict,&config),vec!["学","Rust语言"]);
+        assert!("mm".parse::<SegmentMethod>().is_err());
+    }
+
+    # [test]
+    fn test_max_len_counts_graphemes(){
+        // the window is counted in graphemes, not bytes or chars: 中华人民共和国 is 21 bytes,
+        // and 한국 written with conjoining jamo is six chars but two graphemes
+        let hanguk="\u{1112}\u{1161}\u{11AB}\u{1100}\u{116E}\u{11A8}";
+        let dict=Dictionary::from_words(vec!["中华人民共和国",hanguk]);
+        assert_eq!(dict.max_word_len(),7);
+        let sentence="中华人民共和国".graphemes(true).collect::<Vec<&str>>();
+        assert_eq!(fmm_with_max_len(&sentence,&dict,7),vec!["中华人民共和国"]);
+        assert_eq!(bmm_with_max_len(&sentence,&dict,6).len(),7);
+        let sentence=hanguk.graphemes(true).collect::<Vec<&str>>();
+        assert_eq!(sentence.len(),2);
+        assert_eq!(fmm_with_max_len(&sentence,&dict,2),vec![hanguk]);
+        assert_eq!(bmm_with_max_len(&sentence,&dict,2),vec![hanguk]);
+    }
+
+    # [test]
+    fn test_dag(){
+        let mut dict=Dictionary::new();
+        for (word,freq) in [("有",20000),("有意",500),("意见",3000),("分歧",800),("见",1000),("意",1200)]{
+            dict.insert_entry(word,freq,None);
+        }
+        let sentence=get_word_list("有意见分歧");
+        let dag_graph=get_dag(&sentence,&dict,dict.max_word_len());
+        assert_eq!(dag_graph[0].iter().map(|e| e.0).collect::<Vec<usize>>(),vec![1,2]);
+        // fmm is greedy ("有意/见"), the frequency route prefers "有/意见"
+        assert_eq!(fmm(&sentence,&dict),vec!["有意","见","分歧"]);
+        assert_eq!(dag(&sentence,&dict),vec!["有","意见","分歧"]);
+        assert_eq!(segment("有意见分歧",&dict,"dag"),vec!["有","意见","分歧"]);
+    }
+
+    # [test]
+    fn test_hmm_stage(){
+        let model_path=std::env::temp_dir().join("rsnltk_test_bmes.txt");
+        std::fs::write(&model_path,"start\tB\t-0.5\nstart\tS\t-1.0\ntrans\tB\tE\t-0.3\ntrans\tE\tS\t-0.8\n\
+trans\tS\tB\t-0.5\ntrans\tE\tB\t-0.6\nemit\tB\t李\t-1.0\nemit\tE\t明\t-1.0\n").unwrap();
+        let dict_path=std::env::temp_dir().join("rsnltk_test_dict.txt");
+        std::fs::write(&dict_path,"我\n喜欢\n").unwrap();
+        let mut config=SegmenterConfig{dict_path:dict_path.to_string_lossy().to_string(),..Default::default()};
+        assert_eq!(get_segmentation_with_config("我喜欢李明",&config),vec!["我","喜欢","李","明"]);
+        config.hmm_path=model_path.to_string_lossy().to_string();
+        assert_eq!(get_segmentation_with_config("我喜欢李明",&config),vec!["我","喜欢","李明"]);
+        config.hmm_path="no_such_model.txt".to_string();
+        assert!(try_get_segmentation_with_config("我喜欢李明",&config).is_err());
+    }
+
+    # [test]
+    fn test_trained_model_segmenter(){
+        let dict_path=std::env::temp_dir().join("rsnltk_test_segmenter_dict.txt");
+        std::fs::write(&dict_path,"我\n喜欢\n").unwrap();
+        let config=SegmenterConfig{dict_path:dict_path.to_string_lossy().to_string(),..Default::default()};
+        let segmenter=Segmenter::from_config(&config).unwrap();
+        for _ in 0..3{
+            assert_eq!(segmenter.cut("我喜欢"),vec!["我","喜欢"]);
+        }
+        let config=SegmenterConfig{dict_path:"no_such_dict.txt".to_string(),..Default::default()};
+        assert!(Segmenter::from_config(&config).is_err());
+    }
+
+    # [test]
+    fn test_trained_model(){
+        use rsnltk::native::trainer::train_file;
+        let corpus_path=std::env::temp_dir().join("rsnltk_test_corpus.txt");
+        std::fs::write(&corpus_path,"我 喜欢 北京\n北京 是 首都\n我 爱 吃 苹果\n李明 喜欢 苹果\n").unwrap();
+        let model_path=std::env::temp_dir().join("rsnltk_test_model.txt");
+        let model=train_file(&corpus_path,&model_path).unwrap();
+        assert_eq!(model.dictionary.freq("苹果"),Some(2));
+        let config=SegmenterConfig{
+            model_path:model_path.to_string_lossy().to_string(),
+            method:SegmentMethod::Dag,
+            ..Default::default()
+        };
+        assert_eq!(try_get_segmentation_with_config("我爱北京",&config).unwrap(),vec!["我","爱","北京"]);
+    }
+
+    # [test]
+    fn test_evaluate_methods(){
+        use rsnltk::native::evaluation::evaluate;
+        let dict=Dictionary::from_words(vec!["研究","研究生","生命","命","的","起源"]);
+        let gold=vec![vec!["研究","生命","的","起源"]];
+        for method in ["fmm","bmm","bimm"]{
+            let predicted=vec![segment("研究生命的起源",&dict,method)];
+            let evaluation=evaluate(&gold,&predicted,&dict).unwrap();
+            println!("{}: {:?}",method,evaluation.score);
+            if method=="fmm"{
+                assert_eq!(evaluation.errors[0].spurious,vec!["研究生","命"]);
+            }else{
+                assert_eq!(evaluation.score.f1,1.0);
+            }
+        }
+    }
+
+    # [test]
+    fn test_segmenter(){
+        let mut base=Dictionary::new();
+        for (word,freq) in [("我",5000),("喜欢",3000),("自然",2000),("语言",2000),("处理",2000),("的",9000)]{
+            base.insert_entry(word,freq,None);
+        }
+        let mut segmenter=Segmenter::new(base).method(SegmentMethod::Dag).stop_words(vec!["的"]);
+        assert_eq!(segmenter.cut("我喜欢自然语言处理的"),vec!["我","喜欢","自然","语言","处理"]);
+
+        // user dictionaries: the higher priority wins, a lower one does not override it
+        let user_path=std::env::temp_dir().join("rsnltk_test_user_dict.txt");
+        std::fs::write(&user_path,"自然语言处理\n语言 10 n\n").unwrap();
+        assert_eq!(segmenter.load_user_dictionary(&user_path,10).unwrap(),2);
+        assert_eq!(segmenter.cut("我喜欢自然语言处理"),vec!["我","喜欢","自然语言处理"]);
+        let low=Dictionary::from_words(vec!["语言"]);
+        assert_eq!(segmenter.add_dictionary(&low,5),0);
+        assert_eq!(segmenter.dictionary().freq("语言"),Some(10));
+
+        // runtime edits
+        assert!(segmenter.del_word("自然语言处理"));
+        assert_eq!(segmenter.cut("我喜欢自然语言处理"),vec!["我","喜欢","自然","语言","处理"]);
+        segmenter.add_word("喜欢自然",None,None);
+        assert_eq!(segmenter.cut("我喜欢自然")[1],"喜欢自然");
+        segmenter.suggest_freq(&["喜欢","自然"],true);
+        assert_eq!(segmenter.cut("我喜欢自然"),vec!["我","喜欢","自然"]);
+    }
+
+    # [test]
+    fn test_atomic_units(){
+        assert_eq!(get_atomic_units("我用Rust 1.56写NLP"),vec!["我","用","Rust"," ","1.56","写","NLP"]);
+        assert_eq!(get_atomic_units("增长了12.5%，共3,000人"),vec!["增","长","了","12.5%","，","共","3,000","人"]);
+        assert_eq!(get_atomic_units("见https://github.com/dhchenx/rsnltk。"),vec!["见","https://github.com/dhchenx/rsnltk","。"]);
+        assert_eq!(get_atomic_units("发邮件到dev.team@example.com."),vec!["发","邮","件","到","dev.team@example.com","."]);
+        assert_eq!(get_atomic_units("COVID-19和don't"),vec!["COVID-19","和","don't"]);
+        assert_eq!(get_atomic_units("1.5."),vec!["1.5","."]);
+
+        let dict=Dictionary::from_words(vec!["我","写"]);
+        for method in ["fmm","bmm","bimm","dag"]{
+            assert_eq!(segment("我用Rust 1.56写NLP",&dict,method),vec!["我","用","Rust"," ","1.56","写","NLP"]);
+        }
+        // a dictionary entry can span several units
+        let dict=Dictionary::from_words(vec!["Rust语言"]);
+        assert_eq!(segment("学Rust语言",&dict,"fmm"),vec!["学","Rust语言"]);
+    }
+
+    # [test]
+    fn test_atomic_units_long_input(){
+        // inputs this long only finish quickly when the scan is linear; see benches/segmentation.rs for timings
+        let piece="我用Rust 1.56写NLP，访问www.example.com或写信给dev@example.org。a.b.c ";
+        let per_piece=get_atomic_units(piece).len();
+        let text=piece.repeat(20000);
+        let units=get_atomic_units(&text);
+        assert_eq!(units.len(),per_piece*20000);
+        assert_eq!(units.concat(),text);
+
+        // a single run of letters, digits or whitespace stays one unit however long it is
+        for run in ["a".repeat(200000),"1".repeat(200000)," ".repeat(200000)]{
+            assert_eq!(get_atomic_units(&run),vec![run.as_str()]);
+        }
+    }
+
+    # [test]
+    fn test_spans(){
+        let dict=Dictionary::from_words(vec!["我","喜欢","Rust"]);
+        let sentence="我喜欢Rust和NLP";
+        let spans=segment_spans(sentence,&dict,"fmm");
+        assert_eq!(spans.iter().map(|s| s.text.as_str()).collect::<Vec<&str>>(),vec!["我","喜欢","Rust","和","NLP"]);
+        for span in &spans{
+            assert_eq!(&sentence[span.byte_start..span.byte_end],span.text);
+        }
+        assert_eq!((spans[2].char_start,spans[2].char_end),(3,7));
+        assert_eq!((spans[2].byte_start,spans[2].byte_end),(9,13));
+        assert!(spans[1].in_dict);
+        assert!(!spans[3].in_dict);
+
+        // stop words are left out without shifting the other offsets
+        let segmenter=Segmenter::new(dict).stop_words(vec!["和"]);
+        let spans=segmenter.cut_spans(sentence);
+        assert_eq!(spans.len(),4);
+        assert_eq!(spans[3].text,"NLP");
+        assert_eq!(spans[3].char_start,8);
+    }
+
+    # [test]
+    fn test_pos_tagging(){
+        use rsnltk::native::hmm::Hmm;
+        let mut dict=Dictionary::new();
+        dict.insert_entry("我",10,Some("r"));
+        dict.insert_entry("喜欢",5,Some("v"));
+        dict.insert_entry("北京",5,Some("ns"));
+        let tagged=segment_pos("我喜欢北京和Rust 2.5%",&dict,"bimm");
+        let tags:Vec<&str>=tagged.iter().map(|(_,t)| t.as_str()).collect();
+        assert_eq!(tags,vec!["r","v","ns","x","eng","x","m"]);
+
+        // the tag model labels a word without tag from its neighbours
+        dict.insert("上海");
+        let pos_hmm=Hmm::parse("start\tr\t-0.1\nstart\tv\t-3\nstart\tns\t-3
+trans\tr\tv\t-0.1\ntrans\tv\tns\t-0.5\ntrans\tv\tr\t-1
+emit\tns\t<unk>\t-2\nemit\tr\t<unk>\t-9\nemit\tv\t<unk>\t-9").unwrap();
+        let segmenter=Segmenter::new(dict).pos_hmm(pos_hmm.clone()).stop_words(vec!["我"]);
+        let tagged=segmenter.tag("我喜欢上海");
+        assert_eq!(tagged,vec![("喜欢".to_string(),"v".to_string()),("上海".to_string(),"ns".to_string())]);
+
+        // a tag model file only has the tags it names, not the BMES states
+        let pos_path=std::env::temp_dir().join("rsnltk_test_pos_hmm.txt");
+        std::fs::write(&pos_path,pos_hmm.to_string()).unwrap();
+        let loaded=Hmm::from_file_with_states(&pos_path,&[]).unwrap();
+        assert_eq!(loaded.states(),&["r","v","ns"]);
+        let dict_path=std::env::temp_dir().join("rsnltk_test_pos_dict.txt");
+        std::fs::write(&dict_path,"我 10 r\n喜欢 5 v\n上海 5\n").unwrap();
+        let config=SegmenterConfig{dict_path:dict_path.to_string_lossy().to_string(),pos_hmm_path:pos_path.to_string_lossy().to_string(),..Default::default()};
+        let segmenter=Segmenter::from_config(&config).unwrap();
+        assert_eq!(segmenter.tag("我喜欢上海")[2],("上海".to_string(),"ns".to_string()));
+    }
+
     # [test]
     fn test_real_word_segmentation(){
         let dict_path="D:\\GitHub\\rsnltk\\experiments\\rsnltk-experiment\\examples\\data\\dicts\\30wdict.txt";
@@ -61,9 +308,16 @@ mod tests{
         println!("Result: {:?}",meaningful_words);
     }
 
+    # [test]
+    fn test_try_load_dictionary(){
+        assert!(try_load_dictionary("no_such_dict.txt").is_err());
+        assert!(try_get_segmentation("我喜欢吃苹果","no_such_dict.txt","","bimm").is_err());
+        assert_eq!(try_get_segmentation("我喜欢","","","bimm").unwrap(),vec!["我","喜","欢"]);
+    }
+
     # [test]
     fn test_segmentation_performance(){
-        use std::time::{Duration, Instant};
+        use std::time::Instant;
         // set a dictionary
         let dict_path="30wdict.txt";
         let stop_path="baidu_stopwords.txt";
@@ -71,13 +325,13 @@ mod tests{
         let _sentence="美国太空总署希望，在深海的探险发现将有助于解开一些外太空的秘密，同时也可以测试前往太阳系其他星球探险所需的一些设备和实验。";
         // start to time recording
         let mut start = Instant::now();
-        let bimm_result=get_segmentation(_sentence,dict_path,stop_path, "bimm");
+        let _bimm_result=get_segmentation(_sentence,dict_path,stop_path, "bimm");
         println!("bimm's time cost: {:?}",start.elapsed());
         start = Instant::now();
-        let fmm_result=get_segmentation(_sentence,dict_path,stop_path, "fmm");
+        let _fmm_result=get_segmentation(_sentence,dict_path,stop_path, "fmm");
         println!("fmm's time cost: {:?}",start.elapsed());
         start = Instant::now();
-        let bmm_result=get_segmentation(_sentence,dict_path,stop_path, "bmm");
+        let _bmm_result=get_segmentation(_sentence,dict_path,stop_path, "bmm");
         println!("bmm's time cost: {:?}",start.elapsed());
 
     }
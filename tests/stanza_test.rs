@@ -1,7 +1,10 @@
+#![cfg(feature = "python")]
+
 
 #[cfg(test)]
 mod tests {
-    use rsnltk::{download_lang, ner, tokenize, download_langs, tokenize_sentence, lang, sentiment, mwt_expand, pos, dependency_tree};
+    #[allow(unused_imports)]
+    use rsnltk::{Pipeline, StanzaSession, ner_batch, installed_languages, installed_processors, is_model_installed, missing_processors, download_lang, ner, tokenize, download_langs, tokenize_sentence, lang, sentiment, mwt_expand, pos, dependency_tree};
 
     # [test] // before use the rsnltk library, you need to download target language package from Stanza's website.
     fn test_download_langs(){
@@ -66,6 +69,7 @@ mod tests {
     fn test_mwt_expand(){
         let text="Nous avons atteint la fin du sentier.";
         let lang="fr";
+        #[allow(unused_variables)]
         let list_result=mwt_expand(text,lang);
     }
     # [test]
@@ -98,12 +102,97 @@ mod tests {
         let text="I like you. Do you like me?";
         let lang="en";
         let list_results=dependency_tree(text,lang);
-        for list_token in list_results{
-            for token in list_token{
-                println!("{:?}",token)
+        for sentence in list_results{
+            for word in sentence.words(){
+                println!("{:?}",word)
+            }
+
+        }
+    }
+
+    # [test]
+    fn test_pipeline(){
+        let pipeline=Pipeline::new("en").processors(&["ner","pos","lemma","depparse"]);
+        assert_eq!(pipeline.processor_list(),"tokenize,pos,lemma,depparse,ner");
+        match pipeline.annotate("I like Beijing! Do you like it?"){
+            Ok(doc)=>{
+                for sentence in &doc.sentences{
+                    for word in sentence.words(){
+                        println!("{:?}",word);
+                    }
+                    println!("{:?}",sentence.entities);
+                }
+            },
+            Err(e)=>println!("{:?}",e)
+        }
+    }
+
+    # [test]
+    fn test_session(){
+        let session=match StanzaSession::new(){
+            Ok(session)=>session,
+            Err(e)=>{
+                println!("{:?}",e);
+                return;
             }
+        };
+        for text in ["I like Beijing!","I like New York!"]{
+            match session.ner(text,"en"){
+                Ok(entities)=>println!("{:?}",entities),
+                Err(e)=>println!("{:?}",e)
+            }
+        }
+        println!("{:?}",session.loaded_pipelines());
+    }
+
+    # [test]
+    fn test_ner_batch(){
+        let texts=vec!["I like Beijing!","I like New York!","Paris is nice."];
+        match ner_batch(&texts,"en",Some(2)){
+            Ok(results)=>{
+                assert_eq!(results.len(),texts.len());
+                for (text,entities) in texts.iter().zip(results){
+                    println!("{} => {:?}",text,entities);
+                }
+            },
+            Err(e)=>println!("{:?}",e)
+        }
+    }
+
+    # [test]
+    fn test_offline_models(){
+        use std::fs;
+        use rsnltk::Error;
+        let dir=std::env::temp_dir().join("rsnltk_stanza_resources_test");
+        let _=fs::remove_dir_all(&dir);
+        for processor in ["tokenize","pos","pretrain"]{
+            fs::create_dir_all(dir.join("en").join(processor)).unwrap();
+            fs::write(dir.join("en").join(processor).join("default.pt"),"").unwrap();
+        }
+        // Stanza stores "zh" under its alias "zh-hans"
+        fs::create_dir_all(dir.join("zh-hans").join("tokenize")).unwrap();
+        fs::write(dir.join("zh-hans").join("tokenize").join("gsdsimp.pt"),"").unwrap();
+        fs::create_dir_all(dir.join("fr").join("tokenize")).unwrap();
+
+        assert_eq!(installed_languages(&dir).unwrap(),vec!["en","zh-hans"]);
+        assert_eq!(installed_processors(&dir,"en").unwrap(),vec!["pos","tokenize"]);
+        // mwt only counts as missing when resources.json lists a model for the language
+        assert!(!is_model_installed(&dir,"en",&["tokenize","mwt","pos"]));
+        fs::write(dir.join("resources.json"),r#"{"en":{"tokenize":{},"pos":{}},"fr":{"tokenize":{},"mwt":{}},"zh":{"alias":"zh-hans"},"zh-hans":{"tokenize":{}}}"#).unwrap();
+        assert!(is_model_installed(&dir,"en",&["tokenize","mwt","pos"]));
+        assert!(is_model_installed(&dir,"zh",&["mwt"]));
+        assert!(missing_processors(&dir,"zh",&["tokenize"]).is_empty());
+        assert_eq!(installed_processors(&dir,"zh").unwrap(),vec!["tokenize"]);
+        assert!(Pipeline::new("zh").processor("tokenize").model_dir(&dir).offline(true).check_models().is_ok());
+        assert_eq!(missing_processors(&dir,"fr",&["tokenize","mwt"]),vec!["tokenize","mwt"]);
+        assert!(!is_model_installed(&dir,"en",&["tokenize","ner"]));
 
+        let pipeline=Pipeline::new("en").processors(&["pos","ner"]).model_dir(&dir).offline(true);
+        match pipeline.annotate("I like Beijing!"){
+            Err(Error::ModelNotDownloaded(msg))=>assert!(msg.contains("ner")),
+            other=>panic!("expected a missing model error, got {:?}",other)
         }
+        fs::remove_dir_all(&dir).unwrap();
     }
 
-}
\ No newline at end of file
+}
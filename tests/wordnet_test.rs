@@ -1,3 +1,5 @@
+#![cfg(feature = "python")]
+
 #[cfg(test)]
 mod tests{
     use rsnltk::wordnet::wordnet_similarity;